@@ -1,3 +1,196 @@
+extern crate new_uttt;
+
+mod referee;
+mod tournament;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
+
+use new_uttt::game::game_state::State;
+
+use referee::game::{play_game, Limits};
+use referee::openings;
+use referee::process::BotCommand;
+use tournament::{round_robin, sprt};
+
 fn main() {
-    println!("Hello, world!");
+    let args = std::env::args().collect::<Vec<_>>();
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("play") => {
+            let bot = |index: usize| args.get(index).and_then(|bot| BotCommand::parse(bot));
+            match (bot(2), bot(3)) {
+                (Some(first), Some(second)) => play(&first, &second, args.get(4)),
+                _ => usage(),
+            }
+        }
+        Some("tournament") => tournament(&args[2..]),
+        Some("sprt") => sprt(&args[2..]),
+        Some("openings") => generate_openings(&args[2..]),
+        _ => usage(),
+    }
+}
+
+fn usage() {
+    eprintln!("usage: codingame_server play <bot> <bot> [record]");
+    eprintln!("       codingame_server tournament [--games n] [--threads n] [--records dir] [--grace ms]");
+    eprintln!("                               [--openings file] <bot> <bot>...");
+    eprintln!("       codingame_server sprt [--elo0 0] [--elo1 10] [--alpha 0.05] [--beta 0.05] [--threads n]");
+    eprintln!("                         [--max-pairs n] [--grace ms] [--openings file] <new bot> <base bot>");
+    eprintln!("       codingame_server openings [--count 100] [--plies 4] [--iterations 20000] [--max-bias 0.1]");
+    eprintln!("                             [--seed 1] <file>");
+}
+
+// Splits `--option value` pairs from the arguments that follow them.
+fn options(args: &[String]) -> (Vec<(&str, &str)>, &[String]) {
+    let mut options = Vec::new();
+    let mut rest = args;
+    while let [option, value, tail @ ..] = rest {
+        match option.strip_prefix("--") {
+            Some(option) => options.push((option, value.as_str())),
+            None => break,
+        }
+        rest = tail;
+    }
+    (options, rest)
+}
+
+fn bot_commands(args: &[String]) -> Vec<BotCommand> {
+    args.iter().filter_map(|bot| BotCommand::parse(bot)).collect()
+}
+
+// A match from a broken openings file would not be the intended one.
+fn load_openings(path: &str) -> Vec<State> {
+    match openings::load(Path::new(path)) {
+        Ok(openings) => openings,
+        Err(error) => {
+            eprintln!("cannot read {}: {}", path, error);
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_or<T: std::str::FromStr>(value: &str, default: T) -> T {
+    value.parse().unwrap_or(default)
+}
+
+fn tournament(args: &[String]) {
+    let mut settings = round_robin::Settings {
+        games: 20,
+        threads: 1,
+        limits: Limits::default(),
+        record_dir: None,
+        openings: Vec::new(),
+    };
+    let (options, rest) = options(args);
+    for (option, value) in options {
+        match option {
+            "games" => settings.games = parse_or(value, settings.games),
+            "threads" => settings.threads = parse_or(value, settings.threads),
+            "records" => settings.record_dir = Some(PathBuf::from(value)),
+            "openings" => settings.openings = load_openings(value),
+            "grace" => settings.limits.grace = Duration::from_millis(parse_or(value, 0)),
+            _ => eprintln!("unknown option --{}", option),
+        }
+    }
+
+    let bots = bot_commands(rest);
+    if bots.len() < 2 {
+        usage();
+        return;
+    }
+    let results = round_robin::run(&bots, &settings);
+    print!("{}", round_robin::report(&bots, &results));
+}
+
+// Tests whether the first bot is stronger than the second one.
+fn sprt(args: &[String]) {
+    let mut settings = sprt::Settings {
+        elo0: 0.,
+        elo1: 10.,
+        alpha: 0.05,
+        beta: 0.05,
+        threads: 1,
+        limits: Limits::default(),
+        max_pairs: 0,
+        openings: Vec::new(),
+    };
+    let (options, rest) = options(args);
+    for (option, value) in options {
+        match option {
+            "elo0" => settings.elo0 = parse_or(value, settings.elo0),
+            "elo1" => settings.elo1 = parse_or(value, settings.elo1),
+            "alpha" => settings.alpha = parse_or(value, settings.alpha),
+            "beta" => settings.beta = parse_or(value, settings.beta),
+            "threads" => settings.threads = parse_or(value, settings.threads),
+            "max-pairs" => settings.max_pairs = parse_or(value, settings.max_pairs),
+            "openings" => settings.openings = load_openings(value),
+            "grace" => settings.limits.grace = Duration::from_millis(parse_or(value, 0)),
+            _ => eprintln!("unknown option --{}", option),
+        }
+    }
+    let bots = bot_commands(rest);
+    let [new, base] = &bots[..] else {
+        usage();
+        return;
+    };
+
+    let (decision, wdl) = sprt::run(new, base, &settings);
+    let elo = match wdl.elo() {
+        Some((elo, margin)) => format!("{:+.1} +/- {:.1}", elo, margin),
+        None => "?".to_string(),
+    };
+    println!(
+        "{:?} after {} games: +{}={}-{}, elo {}",
+        decision,
+        wdl.games(),
+        wdl.wins,
+        wdl.draws,
+        wdl.losses,
+        elo
+    );
+}
+
+// Writes balanced start positions for `tournament` and `sprt`.
+fn generate_openings(args: &[String]) {
+    let mut settings = openings::Settings::default();
+    let (options, rest) = options(args);
+    for (option, value) in options {
+        match option {
+            "count" => settings.count = parse_or(value, settings.count),
+            "plies" => settings.plies = parse_or(value, settings.plies),
+            "iterations" => settings.iterations = parse_or(value, settings.iterations),
+            "max-bias" => settings.max_bias = parse_or(value, settings.max_bias),
+            "seed" => settings.seed = parse_or(value, settings.seed),
+            _ => eprintln!("unknown option --{}", option),
+        }
+    }
+    let [path] = rest else {
+        usage();
+        return;
+    };
+
+    let positions = openings::generate(&settings);
+    if positions.len() < settings.count {
+        eprintln!("only found {} openings", positions.len());
+    }
+    if let Err(error) = openings::save(Path::new(path), &positions) {
+        eprintln!("cannot write {}: {}", path, error);
+    }
+}
+
+// One game with stderr of the bots shown, `first` moves first.
+fn play(first: &BotCommand, second: &BotCommand, record_path: Option<&String>) {
+    let outcome = play_game([first, second], None, Limits::default(), true);
+    match outcome.winner {
+        Some(winner) => println!("{} wins by {}", [first, second][winner], outcome.reason),
+        None => println!("draw"),
+    }
+    println!("{} moves", outcome.record.moves.len());
+    if let Some(path) = record_path {
+        if let Err(error) = fs::write(path, outcome.record.to_string()) {
+            eprintln!("cannot write {}: {}", path, error);
+        }
+    }
 }
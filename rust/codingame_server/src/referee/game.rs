@@ -0,0 +1,232 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use new_uttt::codingame::input::START_ENV;
+use new_uttt::game::game_action::Action;
+use new_uttt::game::game_state::{State, UTTTResult};
+use new_uttt::game::masks::WIN_LINES;
+use new_uttt::game::player::Player;
+use new_uttt::game::record::{GameRecord, RecordResult};
+use new_uttt::mcts::traits::GameState;
+
+use super::process::{BotCommand, BotProcess, ReadError};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    pub first_turn: Duration,
+    pub turn: Duration,
+    // added to both limits, for bots that aim at the limit itself
+    pub grace: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            first_turn: Duration::from_millis(1000),
+            turn: Duration::from_millis(100),
+            grace: Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Reason {
+    // three boards in a row
+    Win,
+    // all boards finished, more boards won
+    Tiebreak,
+    Draw,
+    Timeout,
+    InvalidMove(String),
+    Crash,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::Win => write!(f, "win"),
+            Reason::Tiebreak => write!(f, "tiebreak"),
+            Reason::Draw => write!(f, "draw"),
+            Reason::Timeout => write!(f, "timeout"),
+            Reason::InvalidMove(output) => write!(f, "invalid move {:?}", output),
+            Reason::Crash => write!(f, "crash"),
+        }
+    }
+}
+
+pub struct GameOutcome {
+    // index of the winning bot, 0 for the one that moved first
+    pub winner: Option<usize>,
+    pub reason: Reason,
+    pub record: GameRecord,
+}
+
+// Plays one game between two bot processes the way the Codingame referee
+// does: the bot to move gets the last move (-1 -1 on the very first turn),
+// the number of valid actions and the actions, and has to answer "row col"
+// within the time limit. `bots[0]` moves first. A `start` position is passed
+// to the bots in the `START_ENV` variable, bots that ignore it can't play it.
+// Its last move is what `bots[0]` gets first, and bots reading the start
+// position must not play it again.
+pub fn play_game(bots: [&BotCommand; 2], start: Option<&State>, limits: Limits, show_stderr: bool) -> GameOutcome {
+    let mut record = GameRecord::new(&bots[0].name, &bots[1].name);
+    record.start = start.cloned();
+
+    let mut processes = Vec::with_capacity(2);
+    for (index, &bot) in bots.iter().enumerate() {
+        let mut bot = bot.clone();
+        if let Some(start) = start {
+            bot.env.push((START_ENV.to_string(), start.to_string()));
+        }
+        match BotProcess::spawn(&bot, show_stderr) {
+            Ok(process) => processes.push(process),
+            Err(error) => {
+                eprintln!("cannot start {}: {}", bot, error);
+                return forfeit(record, index, Reason::Crash);
+            }
+        }
+    }
+
+    let mut state = record.start_state();
+    let first_mover = state.next_player();
+    let mut last_action: Option<Action> = state.last_action;
+    let mut turns = [0, 0];
+    let mut to_move = 0;
+    while state.playable() {
+        let actions = state.possible_actions();
+        let mut input = match last_action {
+            Some(action) => format!("{}\n", action),
+            None => "-1 -1\n".to_string(),
+        };
+        input.push_str(&format!("{}\n", actions.len()));
+        for action in &actions {
+            input.push_str(&format!("{}\n", action));
+        }
+
+        let process = &mut processes[to_move];
+        if process.send(&input).is_err() {
+            return forfeit(record, to_move, Reason::Crash);
+        }
+        let limit = if turns[to_move] == 0 { limits.first_turn } else { limits.turn } + limits.grace;
+        let (line, time) = match process.read_line(Instant::now(), limit) {
+            Ok(answer) => answer,
+            Err(ReadError::Timeout) => return forfeit(record, to_move, Reason::Timeout),
+            Err(ReadError::Closed) => return forfeit(record, to_move, Reason::Crash),
+        };
+
+        let action = match parse_action(&line) {
+            Some(action) if actions.contains(&action) => action,
+            _ => return forfeit(record, to_move, Reason::InvalidMove(line)),
+        };
+        record.push(action, Some(time));
+        state = state.perform_action_copy(&action);
+        last_action = Some(action);
+        turns[to_move] += 1;
+        to_move = 1 - to_move;
+    }
+
+    record.finish(state.outcome());
+    let (winner, reason) = match state.outcome() {
+        UTTTResult::Won(player) => {
+            let winner = if player == first_mover { 0 } else { 1 };
+            (Some(winner), if has_line(&state, player) { Reason::Win } else { Reason::Tiebreak })
+        }
+        _ => (None, Reason::Draw),
+    };
+    GameOutcome { winner, reason, record }
+}
+
+// Bots may print a message after the move, like on Codingame.
+fn parse_action(line: &str) -> Option<Action> {
+    let mut words = line.split_ascii_whitespace();
+    let row = words.next()?.parse().ok()?;
+    let col = words.next()?.parse().ok()?;
+    Action::from_row_col(row, col).ok()
+}
+
+// Drawn boards count for both players, as in `GlobalBoard::set`.
+fn has_line(state: &State, player: Player) -> bool {
+    let boards = state.global_states.won(player) | state.global_states.drawn();
+    WIN_LINES.iter().any(|&line| line & !boards == 0)
+}
+
+fn forfeit(mut record: GameRecord, loser: usize, reason: Reason) -> GameOutcome {
+    record.result = if loser == 0 {
+        RecordResult::SecondWon
+    } else {
+        RecordResult::FirstWon
+    };
+    GameOutcome {
+        winner: Some(1 - loser),
+        reason,
+        record,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    use super::*;
+
+    // Answers every turn with the first valid action.
+    const FIRST_ACTION: &str = r#"
+while read last; do
+    read count
+    i=0
+    while [ $i -lt $count ]; do
+        read action
+        [ $i -eq 0 ] && first=$action
+        i=$((i + 1))
+    done
+    echo "$first"
+done
+"#;
+
+    // Answers every turn with the center square.
+    const CENTER: &str = r#"
+while read last; do
+    read count
+    i=0
+    while [ $i -lt $count ]; do
+        read action
+        i=$((i + 1))
+    done
+    echo "4 4"
+done
+"#;
+
+    fn script_bot(name: &str, script: &str) -> (BotCommand, PathBuf) {
+        let path = std::env::temp_dir().join(format!("referee_{}_{}.sh", name, process::id()));
+        fs::write(&path, script).unwrap();
+        (BotCommand::parse(&format!("sh {}", path.display())).unwrap(), path)
+    }
+
+    #[test]
+    fn scripted_bots_finish_a_game() {
+        let (bot, path) = script_bot("first", FIRST_ACTION);
+        let outcome = play_game([&bot, &bot], None, Limits::default(), false);
+        fs::remove_file(path).unwrap();
+
+        assert!(matches!(outcome.reason, Reason::Win | Reason::Tiebreak | Reason::Draw), "{}", outcome.reason);
+        let states = outcome.record.replay().unwrap();
+        let end = states.last().unwrap();
+        assert!(!end.playable());
+        assert_eq!(outcome.record.result, RecordResult::from_outcome(end.outcome(), Player::O));
+    }
+
+    #[test]
+    fn illegal_move_loses() {
+        let (bot, path) = script_bot("center", CENTER);
+        let outcome = play_game([&bot, &bot], None, Limits::default(), false);
+        fs::remove_file(path).unwrap();
+
+        // the second bot plays on the square the first one took
+        assert_eq!(outcome.winner, Some(0));
+        assert_eq!(outcome.reason, Reason::InvalidMove("4 4".to_string()));
+        assert_eq!(outcome.record.moves.len(), 1);
+        assert_eq!(outcome.record.result, RecordResult::FirstWon);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod process;
+pub mod game;
+pub mod openings;
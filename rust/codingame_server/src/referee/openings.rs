@@ -0,0 +1,207 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use new_uttt::cg_rand::Rng;
+use new_uttt::game::game_action::Action;
+use new_uttt::game::game_state::State;
+use new_uttt::mcts::traits::GameState;
+use new_uttt::mcts::tree::MctsTree;
+
+#[derive(Clone, Debug)]
+pub struct Settings {
+    pub count: usize,
+    // random moves from the empty board
+    pub plies: usize,
+    // search per candidate to judge its balance
+    pub iterations: u32,
+    // largest distance of the expected score from 1/2 that is kept
+    pub max_bias: f64,
+    pub seed: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            count: 100,
+            plies: 4,
+            iterations: 20_000,
+            max_bias: 0.1,
+            seed: 1,
+        }
+    }
+}
+
+// Random openings of `plies` moves that a short search considers about even,
+// no two of them equal up to a symmetry of the board.
+pub fn generate(settings: &Settings) -> Vec<State> {
+    let mut rng: Box<Rng> = Box::new(Rng::with_seed(settings.seed));
+    let mut seen = HashSet::new();
+    let mut openings = Vec::new();
+    // the first plies have few distinct positions, don't retry forever
+    let mut attempts = settings.count * 100;
+    while openings.len() < settings.count && attempts > 0 {
+        attempts -= 1;
+        let Some(state) = random_opening(settings.plies, &mut rng) else {
+            continue;
+        };
+        if !seen.insert(state.canonical_hash()) {
+            continue;
+        }
+        let score = evaluate(&state, settings.iterations, &mut rng);
+        if (score - 0.5).abs() <= settings.max_bias {
+            eprintln!("{}/{} {} ({:.3})", openings.len() + 1, settings.count, state, score);
+            openings.push(state);
+        }
+    }
+    openings
+}
+
+fn random_opening(plies: usize, rng: &mut Box<Rng>) -> Option<State> {
+    let mut state = State::default();
+    for _ in 0..plies {
+        let actions = state.possible_actions();
+        let action = rng.choice(actions.iter())?;
+        state = state.perform_action_copy(action);
+        if !state.playable() {
+            return None;
+        }
+    }
+    Some(state)
+}
+
+// Expected score of the player to move, from the most visited reply.
+pub fn evaluate(state: &State, iterations: u32, rng: &mut Box<Rng>) -> f64 {
+    let mcts = MctsTree::new(state.clone());
+    mcts.expand_iterations(iterations, rng);
+    let root = Rc::clone(&mcts.root.borrow());
+    let children = root.children.borrow();
+    children
+        .values()
+        .max_by(|a, b| a.visits.get().total_cmp(&b.visits.get()))
+        .map_or(0.5, |best| best.wins.get() / best.visits.get())
+}
+
+// One position per line, in `State` notation followed by the "row col" of
+// the last move, which the referee sends to the first bot to move. `#` starts
+// a comment.
+pub fn load(path: &Path) -> io::Result<Vec<State>> {
+    let text = fs::read_to_string(path)?;
+    let mut openings = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        match parse_opening(line) {
+            Ok(state) => openings.push(state),
+            Err(error) => {
+                let message = format!("line {}: {}", number + 1, error);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+            }
+        }
+    }
+    Ok(openings)
+}
+
+fn parse_opening(line: &str) -> Result<State, String> {
+    let fields = line.split_ascii_whitespace().collect::<Vec<_>>();
+    let (notation, last_move) = match fields[..] {
+        [_, _, _] => (&fields[..], None),
+        [_, _, _, row, col] => (&fields[..3], Some((row, col))),
+        _ => return Err("expected a position and the row and column of its last move".to_string()),
+    };
+    let mut state = notation.join(" ").parse::<State>().map_err(|error| error.to_string())?;
+
+    let action = match last_move {
+        Some((row, col)) => {
+            let coords = row.parse().ok().zip(col.parse().ok());
+            coords
+                .and_then(|(row, col)| Action::from_row_col(row, col).ok())
+                .ok_or_else(|| format!("bad last move {} {}", row, col))?
+        }
+        // the empty board is the only position without a last move
+        None if state.empty_squares() == 81 => return Ok(state),
+        None => return Err("the last move of the opening is missing".to_string()),
+    };
+    if !could_be_last_move(&state, action) {
+        return Err(format!("{} can't be the last move of the position", action));
+    }
+    state.last_action = Some(action);
+    Ok(state)
+}
+
+// The notation does not keep the order of the moves, so this only checks
+// that the square belongs to the player who just moved and sends the next
+// move to the board the position says it does.
+fn could_be_last_move(state: &State, action: Action) -> bool {
+    let (row, col) = action.to_row_col();
+    let target = action.index() % 9;
+    let target_open = state.global_states.in_play(target);
+    state.square(row, col) == Some(state.current_player())
+        && match state.forced_board() {
+            Some(forced) => target == forced,
+            None => !target_open,
+        }
+}
+
+pub fn save(path: &Path, openings: &[State]) -> io::Result<()> {
+    let text = openings
+        .iter()
+        .map(|state| match state.last_action {
+            Some(action) => format!("{} {}\n", state, action),
+            None => format!("{}\n", state),
+        })
+        .collect::<String>();
+    fs::write(path, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_openings_are_legal() {
+        let settings = Settings {
+            count: 6,
+            iterations: 500,
+            max_bias: 0.5,
+            ..Settings::default()
+        };
+        let openings = generate(&settings);
+        assert_eq!(openings.len(), settings.count);
+
+        let hashes = openings.iter().map(State::canonical_hash).collect::<HashSet<_>>();
+        assert_eq!(hashes.len(), openings.len());
+        for state in &openings {
+            assert!(state.playable(), "{}", state);
+            assert_eq!(state.empty_squares(), 81 - settings.plies, "{}", state);
+            assert!(could_be_last_move(state, state.last_action.unwrap()), "{}", state);
+        }
+
+        let path = std::env::temp_dir().join(format!("openings_test_{}.txt", std::process::id()));
+        save(&path, &openings).unwrap();
+        let loaded = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.len(), openings.len());
+        for (loaded, state) in loaded.iter().zip(&openings) {
+            assert_eq!(loaded.to_string(), state.to_string());
+            assert_eq!(loaded.last_action, state.last_action);
+            assert_eq!(loaded.possible_actions(), state.possible_actions());
+        }
+    }
+
+    #[test]
+    fn rejects_impossible_last_moves() {
+        assert!(parse_opening(&State::default().to_string()).is_ok());
+        let state = State::default().perform_action_copy(&Action::from_row_col(4, 4).unwrap());
+        assert!(parse_opening(&format!("{} 4 4", state)).is_ok());
+        assert!(parse_opening(&state.to_string()).is_err());
+        // an empty square, then a square of the player to move
+        assert!(parse_opening(&format!("{} 0 0", state)).is_err());
+        let state = state.perform_action_copy(&Action::from_row_col(3, 3).unwrap());
+        assert!(parse_opening(&format!("{} 4 4", state)).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How to start a bot: a program and its arguments, written like on a shell
+// command line without quoting, e.g. "target/release/new_uttt --no-ponder" or
+// "python3 bots/ultimate_tictactoe.py".
+#[derive(Clone, Debug)]
+pub struct BotCommand {
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+    // extra environment variables
+    pub env: Vec<(String, String)>,
+}
+
+impl BotCommand {
+    pub fn parse(command: &str) -> Option<Self> {
+        let mut words = command.split_ascii_whitespace().map(str::to_string);
+        let program = words.next()?;
+        Some(BotCommand {
+            name: command.to_string(),
+            program,
+            args: words.collect(),
+            env: Vec::new(),
+        })
+    }
+}
+
+impl fmt::Display for BotCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Debug)]
+pub enum ReadError {
+    Timeout,
+    // the bot exited or closed its stdout
+    Closed,
+}
+
+// A running bot. Its stdout is read on a separate thread so that answers can
+// be waited for with a time limit. The process is killed when dropped.
+pub struct BotProcess {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl BotProcess {
+    pub fn spawn(command: &BotCommand, show_stderr: bool) -> io::Result<Self> {
+        let mut child = Command::new(&command.program)
+            .args(&command.args)
+            .envs(command.env.iter().map(|(key, value)| (key, value)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(if show_stderr { Stdio::inherit() } else { Stdio::null() })
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
+
+        Ok(BotProcess { child, stdin, lines })
+    }
+
+    pub fn send(&mut self, input: &str) -> io::Result<()> {
+        self.stdin.write_all(input.as_bytes())?;
+        self.stdin.flush()
+    }
+
+    // The next line the bot prints, and how long it took since `since`.
+    pub fn read_line(&self, since: Instant, limit: Duration) -> Result<(String, Duration), ReadError> {
+        let remaining = limit.saturating_sub(since.elapsed());
+        match self.lines.recv_timeout(remaining) {
+            Ok(line) => Ok((line, since.elapsed())),
+            Err(RecvTimeoutError::Timeout) => Err(ReadError::Timeout),
+            Err(RecvTimeoutError::Disconnected) => Err(ReadError::Closed),
+        }
+    }
+}
+
+impl Drop for BotProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
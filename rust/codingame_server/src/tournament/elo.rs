@@ -0,0 +1,180 @@
+// 95% two-sided normal quantile
+const Z_95: f64 = 1.959_964;
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Wdl {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Wdl {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    // Points per game, a draw is worth half a win.
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.) / self.games() as f64
+    }
+
+    pub fn reversed(&self) -> Wdl {
+        Wdl {
+            wins: self.losses,
+            draws: self.draws,
+            losses: self.wins,
+        }
+    }
+
+    pub fn add(&mut self, other: Wdl) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+
+    // Elo difference implied by the score and the half width of its 95%
+    // confidence interval, from the normal approximation of the per game
+    // score. `None` before the first game.
+    pub fn elo(&self) -> Option<(f64, f64)> {
+        let games = self.games() as f64;
+        if games == 0. {
+            return None;
+        }
+        let score = self.score();
+        let variance = (self.wins as f64 * (1. - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / games;
+        let margin = Z_95 * (variance / games).sqrt();
+        let low = elo_from_score(score - margin);
+        let high = elo_from_score(score + margin);
+        // a perfect score has no finite interval
+        let half_width = if margin > 0. { (high - low) / 2. } else { f64::INFINITY };
+        Some((elo_from_score(score), half_width))
+    }
+}
+
+// Elo difference for an expected score, infinite for 0 and 1.
+pub fn elo_from_score(score: f64) -> f64 {
+    let score = score.clamp(0., 1.);
+    -400. * (1. / score - 1.).log10()
+}
+
+pub fn score_from_elo(elo: f64) -> f64 {
+    1. / (1. + 10_f64.powf(-elo / 400.))
+}
+
+// Maximum likelihood ratings of a Bradley-Terry model, draws counting half,
+// fitted with the minorization-maximization iteration. `results[i][j]` is the
+// result of bot i against bot j. The ratings are Elo with a mean of zero. A
+// bot that never lost or never scored has no finite rating and ends up far
+// out after the last iteration.
+pub fn bradley_terry(results: &[Vec<Wdl>]) -> Vec<f64> {
+    let bots = results.len();
+    let points = results
+        .iter()
+        .map(|row| row.iter().map(|wdl| wdl.wins as f64 + wdl.draws as f64 / 2.).sum::<f64>())
+        .collect::<Vec<_>>();
+
+    let mut strengths = vec![1_f64; bots];
+    for _ in 0..1000 {
+        let mut next = (0..bots)
+            .map(|i| {
+                let denominator = (0..bots)
+                    .filter(|&j| j != i)
+                    .map(|j| results[i][j].games() as f64 / (strengths[i] + strengths[j]))
+                    .sum::<f64>();
+                if denominator > 0. {
+                    points[i] / denominator
+                } else {
+                    strengths[i]
+                }
+            })
+            .collect::<Vec<_>>();
+        let finite = next.iter().filter(|strength| strength.is_finite()).collect::<Vec<_>>();
+        let mean = finite.iter().copied().sum::<f64>() / finite.len().max(1) as f64;
+        if mean > 0. {
+            next.iter_mut().for_each(|strength| *strength /= mean);
+        }
+        let converged = next.iter().zip(&strengths).all(|(a, b)| (a - b).abs() < 1e-9);
+        strengths = next;
+        if converged {
+            break;
+        }
+    }
+
+    let elos = strengths.iter().map(|strength| 400. * strength.log10()).collect::<Vec<_>>();
+    let finite = elos.iter().filter(|elo| elo.is_finite()).collect::<Vec<_>>();
+    let mean = finite.iter().copied().sum::<f64>() / finite.len().max(1) as f64;
+    elos.iter().map(|elo| elo - mean).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wdl(wins: u32, draws: u32, losses: u32) -> Wdl {
+        Wdl { wins, draws, losses }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn score_and_elo_are_inverse() {
+        for elo in [-600., -100., 0., 35., 400.] {
+            assert!(close(elo_from_score(score_from_elo(elo)), elo));
+        }
+        assert!(close(score_from_elo(0.), 0.5));
+        // three times as many points as the opponent
+        assert!(close(elo_from_score(0.75), 400. * 3_f64.log10()));
+        assert_eq!(elo_from_score(1.), f64::INFINITY);
+        assert_eq!(elo_from_score(0.), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn wdl_score_and_interval() {
+        assert_eq!(wdl(0, 0, 0).elo(), None);
+        let result = wdl(6, 4, 2);
+        assert!(close(result.score(), 8. / 12.));
+        assert_eq!(result.reversed(), wdl(2, 4, 6));
+
+        let (elo, margin) = result.elo().unwrap();
+        assert!(close(elo, elo_from_score(8. / 12.)));
+        assert!(margin > 0. && margin.is_finite());
+        // more games of the same kind narrow the interval
+        let (_, narrower) = wdl(60, 40, 20).elo().unwrap();
+        assert!(narrower < margin);
+        assert_eq!(wdl(3, 0, 0).elo().unwrap().1, f64::INFINITY);
+    }
+
+    #[test]
+    fn bradley_terry_two_bots() {
+        let results = vec![vec![Wdl::default(), wdl(3, 0, 1)], vec![wdl(1, 0, 3), Wdl::default()]];
+        let elos = bradley_terry(&results);
+        assert!(close(elos[0] - elos[1], 400. * 3_f64.log10()));
+        assert!(close(elos[0] + elos[1], 0.));
+    }
+
+    #[test]
+    fn bradley_terry_keeps_draws_even() {
+        let results = vec![vec![Wdl::default(), wdl(0, 4, 0)], vec![wdl(0, 4, 0), Wdl::default()]];
+        assert!(bradley_terry(&results).iter().all(|&elo| close(elo, 0.)));
+    }
+
+    // A bot that never scored has no finite rating, the others still average
+    // to zero among themselves.
+    #[test]
+    fn bradley_terry_with_a_scoreless_bot() {
+        let results = vec![
+            vec![Wdl::default(), wdl(3, 0, 1), wdl(4, 0, 0)],
+            vec![wdl(1, 0, 3), Wdl::default(), wdl(4, 0, 0)],
+            vec![wdl(0, 0, 4), wdl(0, 0, 4), Wdl::default()],
+        ];
+        let elos = bradley_terry(&results);
+        assert_eq!(elos[2], f64::NEG_INFINITY);
+        assert!(close(elos[0] + elos[1], 0.));
+        assert!(close(elos[0] - elos[1], 400. * 3_f64.log10()));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod elo;
+pub mod round_robin;
+pub mod sprt;
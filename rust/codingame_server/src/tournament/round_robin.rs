@@ -0,0 +1,187 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use new_uttt::game::game_state::State;
+
+use crate::referee::game::{play_game, GameOutcome, Limits};
+use crate::referee::process::BotCommand;
+
+use super::elo::{bradley_terry, Wdl};
+
+#[derive(Clone)]
+pub struct Settings {
+    // games per pairing, colours alternate between them
+    pub games: usize,
+    pub threads: usize,
+    pub limits: Limits,
+    pub record_dir: Option<PathBuf>,
+    // start positions, each one played twice in a row with colours swapped
+    pub openings: Vec<State>,
+}
+
+struct Job {
+    first: usize,
+    second: usize,
+    game: usize,
+}
+
+// Plays every bot against every other one, games in parallel, and returns
+// `results[i][j]`, the result of bot i against bot j.
+pub fn run(bots: &[BotCommand], settings: &Settings) -> Vec<Vec<Wdl>> {
+    let mut jobs = jobs(bots.len(), settings.games);
+    jobs.reverse();
+    let total = jobs.len();
+
+    let jobs = Arc::new(Mutex::new(jobs));
+    let results = Arc::new(Mutex::new(vec![vec![Wdl::default(); bots.len()]; bots.len()]));
+    let played = Arc::new(Mutex::new(0));
+    let workers = (0..settings.threads.max(1))
+        .map(|_| {
+            let (jobs, results, played) = (Arc::clone(&jobs), Arc::clone(&results), Arc::clone(&played));
+            let (bots, settings) = (bots.to_vec(), settings.clone());
+            thread::spawn(move || loop {
+                let job = match jobs.lock().unwrap().pop() {
+                    Some(job) => job,
+                    None => return,
+                };
+                let start = opening(&settings.openings, job.game / 2);
+                let outcome = play_game([&bots[job.first], &bots[job.second]], start, settings.limits, false);
+                save(&outcome, &job, &settings);
+
+                let result = match outcome.winner {
+                    Some(0) => Wdl { wins: 1, ..Wdl::default() },
+                    Some(_) => Wdl { losses: 1, ..Wdl::default() },
+                    None => Wdl { draws: 1, ..Wdl::default() },
+                };
+                let mut results = results.lock().unwrap();
+                results[job.first][job.second].add(result);
+                results[job.second][job.first].add(result.reversed());
+
+                let mut played = played.lock().unwrap();
+                *played += 1;
+                let summary = match outcome.winner {
+                    Some(winner) => format!("{} wins by {}", [&bots[job.first], &bots[job.second]][winner], outcome.reason),
+                    None => "draw".to_string(),
+                };
+                eprintln!("{}/{} {} vs {}: {}", played, total, bots[job.first], bots[job.second], summary);
+            })
+        })
+        .collect::<Vec<_>>();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    Arc::try_unwrap(results).unwrap().into_inner().unwrap()
+}
+
+// `games` games for every two bots, colours swapped after every game.
+fn jobs(bots: usize, games: usize) -> Vec<Job> {
+    let mut jobs = Vec::new();
+    for i in 0..bots {
+        for j in i + 1..bots {
+            for game in 0..games {
+                let (first, second) = if game % 2 == 0 { (i, j) } else { (j, i) };
+                jobs.push(Job { first, second, game });
+            }
+        }
+    }
+    jobs
+}
+
+// Openings are used in order and start over when all of them were played.
+pub fn opening(openings: &[State], pair: usize) -> Option<&State> {
+    if openings.is_empty() {
+        None
+    } else {
+        Some(&openings[pair % openings.len()])
+    }
+}
+
+fn save(outcome: &GameOutcome, job: &Job, settings: &Settings) {
+    if let Some(dir) = &settings.record_dir {
+        let path = dir.join(format!("{}-{}-{}.txt", job.first, job.second, job.game));
+        if let Err(error) = fs::write(&path, outcome.record.to_string()) {
+            eprintln!("cannot write {}: {}", path.display(), error);
+        }
+    }
+}
+
+// The win/draw/loss table and the bots ranked by rating, with the Elo of
+// each bot against the rest of the field and its 95% confidence interval.
+pub fn report(bots: &[BotCommand], results: &[Vec<Wdl>]) -> String {
+    let mut report = String::new();
+    for (i, bot) in bots.iter().enumerate() {
+        report.push_str(&format!("{:>2} {}\n", i, bot));
+    }
+    report.push('\n');
+
+    report.push_str("  ");
+    for j in 0..bots.len() {
+        report.push_str(&format!(" {:>11}", j));
+    }
+    report.push('\n');
+    for (i, row) in results.iter().enumerate() {
+        report.push_str(&format!("{:>2}", i));
+        for (j, wdl) in row.iter().enumerate() {
+            if i == j {
+                report.push_str(&format!(" {:>11}", "-"));
+            } else {
+                report.push_str(&format!(" {:>11}", format!("+{}={}-{}", wdl.wins, wdl.draws, wdl.losses)));
+            }
+        }
+        report.push('\n');
+    }
+    report.push('\n');
+
+    let ratings = bradley_terry(results);
+    let mut ranking = (0..bots.len()).collect::<Vec<_>>();
+    ranking.sort_by(|&a, &b| ratings[b].total_cmp(&ratings[a]));
+    for (rank, &i) in ranking.iter().enumerate() {
+        let mut total = Wdl::default();
+        results[i].iter().for_each(|&wdl| total.add(wdl));
+        let interval = match total.elo() {
+            Some((elo, margin)) => format!("{:+.0} +/- {:.0} vs field", elo, margin),
+            None => "no games".to_string(),
+        };
+        report.push_str(&format!(
+            "{}. {:+.0} {} ({} games, +{}={}-{}, {})\n",
+            rank + 1,
+            ratings[i],
+            bots[i],
+            total.games(),
+            total.wins,
+            total.draws,
+            total.losses,
+            interval,
+        ));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use new_uttt::game::game_action::Action;
+    use new_uttt::mcts::traits::GameState;
+
+    use super::*;
+
+    #[test]
+    fn colour_swapped_games_share_an_opening() {
+        let openings = [(4, 4), (0, 0), (3, 5)]
+            .map(|(row, col)| State::default().perform_action_copy(&Action::from_row_col(row, col).unwrap()));
+        let jobs = jobs(3, 8);
+        assert_eq!(jobs.len(), 3 * 8);
+        for pair in jobs.chunks(2) {
+            let [game, swapped] = pair else { unreachable!() };
+            assert_eq!((game.first, game.second), (swapped.second, swapped.first));
+            assert_eq!(game.game + 1, swapped.game);
+
+            let start = opening(&openings, game.game / 2).unwrap();
+            assert_eq!(opening(&openings, swapped.game / 2).unwrap().to_string(), start.to_string());
+            assert_eq!(start.to_string(), openings[game.game / 2 % openings.len()].to_string());
+        }
+        assert!(opening(&[], 0).is_none());
+    }
+}
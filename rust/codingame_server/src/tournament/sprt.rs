@@ -0,0 +1,215 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use new_uttt::game::game_state::State;
+
+use crate::referee::game::{play_game, Limits};
+use crate::referee::process::BotCommand;
+
+use super::elo::{score_from_elo, Wdl};
+use super::round_robin::opening;
+
+#[derive(Clone)]
+pub struct Settings {
+    // Elo of the new engine over the base under the null and the alternative
+    // hypothesis
+    pub elo0: f64,
+    pub elo1: f64,
+    // false positive and false negative rates
+    pub alpha: f64,
+    pub beta: f64,
+    pub threads: usize,
+    pub limits: Limits,
+    // gives up undecided after this many pairs, 0 for no limit
+    pub max_pairs: usize,
+    // start positions, one per pair
+    pub openings: Vec<State>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Decision {
+    // the new engine is at least `elo1` stronger
+    AcceptH1,
+    // the new engine is at most `elo0` stronger
+    AcceptH0,
+    Undecided,
+}
+
+// Game pairs counted by the points the new engine scored in them, 0 to 2 in
+// steps of a half. The two games of a pair share their opening, so they are
+// not independent, but the pairs are.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Pentanomial {
+    pub pairs: [u32; 5],
+}
+
+impl Pentanomial {
+    pub fn add(&mut self, pair: Wdl) {
+        let half_points = 2 * pair.wins + pair.draws;
+        self.pairs[half_points as usize] += 1;
+    }
+
+    pub fn total(&self) -> u32 {
+        self.pairs.iter().sum()
+    }
+}
+
+impl Settings {
+    // Wald's bounds for the log-likelihood ratio.
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1. - self.alpha)).ln(), ((1. - self.beta) / self.alpha).ln())
+    }
+}
+
+// Log-likelihood ratio of elo1 against elo0 for the game pairs of the new
+// engine, with the usual normal approximation of the pentanomial model: the
+// observed variance of the per game score of a pair and the score expected
+// under each hypothesis.
+pub fn llr(pentanomial: Pentanomial, elo0: f64, elo1: f64) -> f64 {
+    let pairs = pentanomial.total() as f64;
+    if pairs == 0. {
+        return 0.;
+    }
+    let frequencies = pentanomial.pairs.iter().map(|&count| count as f64 / pairs).collect::<Vec<_>>();
+    let pair_score = |half_points: usize| half_points as f64 / 4.;
+    let score = frequencies.iter().enumerate().map(|(k, p)| p * pair_score(k)).sum::<f64>();
+    let variance = frequencies
+        .iter()
+        .enumerate()
+        .map(|(k, p)| p * (pair_score(k) - score).powi(2))
+        .sum::<f64>();
+    if variance <= 0. {
+        // all pairs equal, no estimate of the spread yet
+        return 0.;
+    }
+    let (score0, score1) = (score_from_elo(elo0), score_from_elo(elo1));
+    pairs * (score1 - score0) * (2. * score - score0 - score1) / (2. * variance)
+}
+
+// Plays pairs of games between `new` and `base`, colours swapped within a
+// pair, until the test on the pair results decides. Pairs run in parallel, so a few more games
+// than needed may finish after the decision and still count.
+pub fn run(new: &BotCommand, base: &BotCommand, settings: &Settings) -> (Decision, Wdl) {
+    let (lower, upper) = settings.bounds();
+    let total = Arc::new(Mutex::new((Wdl::default(), Pentanomial::default())));
+    let done = Arc::new(AtomicBool::new(false));
+    let next_pair = Arc::new(AtomicUsize::new(0));
+
+    let workers = (0..settings.threads.max(1))
+        .map(|_| {
+            let (total, done, next_pair) = (Arc::clone(&total), Arc::clone(&done), Arc::clone(&next_pair));
+            let (new, base, settings) = (new.clone(), base.clone(), settings.clone());
+            thread::spawn(move || {
+                while !done.load(Ordering::SeqCst) {
+                    let mut pair = Wdl::default();
+                    let start = opening(&settings.openings, next_pair.fetch_add(1, Ordering::SeqCst));
+                    for (bots, new_index) in [([&new, &base], 0), ([&base, &new], 1)] {
+                        let outcome = play_game(bots, start, settings.limits, false);
+                        match outcome.winner {
+                            Some(winner) if winner == new_index => pair.wins += 1,
+                            Some(_) => pair.losses += 1,
+                            None => pair.draws += 1,
+                        }
+                    }
+
+                    let mut total = total.lock().unwrap();
+                    total.0.add(pair);
+                    total.1.add(pair);
+                    let (wdl, pentanomial) = *total;
+                    let pairs = pentanomial.total() as usize;
+                    let ratio = llr(pentanomial, settings.elo0, settings.elo1);
+                    eprintln!(
+                        "pairs {} {:?} +{}={}-{} llr {:.3} ({:.3}, {:.3})",
+                        pairs, pentanomial.pairs, wdl.wins, wdl.draws, wdl.losses, ratio, lower, upper
+                    );
+                    if ratio <= lower || ratio >= upper || (settings.max_pairs > 0 && pairs >= settings.max_pairs) {
+                        done.store(true, Ordering::SeqCst);
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    let (wdl, pentanomial) = *total.lock().unwrap();
+    let ratio = llr(pentanomial, settings.elo0, settings.elo1);
+    let decision = if ratio >= upper {
+        Decision::AcceptH1
+    } else if ratio <= lower {
+        Decision::AcceptH0
+    } else {
+        Decision::Undecided
+    };
+    (decision, wdl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pentanomial(pairs: [u32; 5]) -> Pentanomial {
+        Pentanomial { pairs }
+    }
+
+    #[test]
+    fn wald_bounds() {
+        let settings = Settings {
+            elo0: 0.,
+            elo1: 10.,
+            alpha: 0.05,
+            beta: 0.05,
+            threads: 1,
+            limits: Limits::default(),
+            max_pairs: 0,
+            openings: Vec::new(),
+        };
+        let (lower, upper) = settings.bounds();
+        assert!((upper - 19_f64.ln()).abs() < 1e-9);
+        assert!((lower + 19_f64.ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pairs_are_counted_by_points() {
+        let mut counts = Pentanomial::default();
+        for (wins, draws, losses) in [(0, 0, 2), (0, 1, 1), (1, 0, 1), (0, 2, 0), (1, 1, 0), (2, 0, 0)] {
+            counts.add(Wdl { wins, draws, losses });
+        }
+        assert_eq!(counts.pairs, [1, 1, 2, 1, 1]);
+        assert_eq!(counts.total(), 6);
+    }
+
+    #[test]
+    fn no_evidence_without_spread() {
+        assert_eq!(llr(Pentanomial::default(), 0., 10.), 0.);
+        // every pair split, the pairs carry no variance to judge by
+        assert_eq!(llr(pentanomial([0, 0, 50, 0, 0]), 0., 10.), 0.);
+    }
+
+    #[test]
+    fn llr_follows_the_score() {
+        let (elo0, elo1) = (0., 10.);
+        let even = pentanomial([5, 20, 50, 20, 5]);
+        let strong = pentanomial([5, 10, 50, 30, 5]);
+        let weak = pentanomial([5, 30, 50, 10, 5]);
+        assert!(llr(even, elo0, elo1) < 0.);
+        assert!(llr(strong, elo0, elo1) > 0.);
+        assert!(llr(weak, elo0, elo1) < llr(even, elo0, elo1));
+
+        // the same results twice as often are twice the evidence
+        let doubled = pentanomial(strong.pairs.map(|count| count * 2));
+        assert!((llr(doubled, elo0, elo1) - 2. * llr(strong, elo0, elo1)).abs() < 1e-9);
+    }
+
+    // The normal approximation for a hand computed sample: pair scores of
+    // 0.25 and 0.75 equally often, a mean of 1/2 and a variance of 1/16. With
+    // elo0 at an even score the ratio is -n (s1 - 1/2)^2 / (2 variance).
+    #[test]
+    fn llr_value() {
+        let score1 = score_from_elo(10.);
+        let expected = -100. * (score1 - 0.5).powi(2) * 8.;
+        assert!((llr(pentanomial([0, 50, 0, 50, 0]), 0., 10.) - expected).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod search;
+pub mod traits;
+pub mod tt;
+
+pub use super::*;
@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::time::{self, Instant};
+
+use super::mcts::traits::*;
+
+use super::traits::{Evaluator, PositionKey};
+use super::tt::{Bound, Entry, TranspositionTable};
+
+pub const WIN: i32 = 1_000_000;
+pub const INFINITY: i32 = WIN + 1;
+pub const MAX_DEPTH: usize = 64;
+
+const TT_SIZE: usize = 1 << 20;
+
+pub struct SearchResult<A: GameAction> {
+    pub best: A,
+    pub score: i32,
+    pub depth: usize,
+    pub nodes: u64,
+}
+
+// Iterative deepening negamax with alpha-beta pruning. Scores are always from
+// the point of view of the player about to move.
+pub struct AlphaBeta<P, S, R, A, E>
+where
+    P: GamePlayer<R>,
+    S: GameState<P, R, A> + PositionKey,
+    R: GameResult,
+    A: GameAction,
+    E: Evaluator<P, S, R, A>,
+{
+    pub evaluator: E,
+    pub tt: TranspositionTable<A>,
+    pub max_depth: usize,
+
+    killers: Vec<[Option<A>; 2]>,
+    history: HashMap<A, i32>,
+    nodes: u64,
+
+    begin: Instant,
+    duration: time::Duration,
+    aborted: bool,
+
+    types: PhantomData<(P, S, R)>,
+}
+
+impl<P, S, R, A, E> AlphaBeta<P, S, R, A, E>
+where
+    P: GamePlayer<R>,
+    S: GameState<P, R, A> + PositionKey,
+    R: GameResult,
+    A: GameAction,
+    E: Evaluator<P, S, R, A>,
+{
+    pub fn new(evaluator: E) -> Self {
+        AlphaBeta {
+            evaluator,
+            tt: TranspositionTable::new(TT_SIZE),
+            max_depth: MAX_DEPTH,
+            killers: vec![[None, None]; MAX_DEPTH + 1],
+            history: HashMap::new(),
+            nodes: 0,
+            begin: Instant::now(),
+            duration: time::Duration::ZERO,
+            aborted: false,
+            types: PhantomData,
+        }
+    }
+
+    pub fn search(
+        &mut self,
+        state: &S,
+        begin: Instant,
+        duration: time::Duration,
+    ) -> Option<SearchResult<A>> {
+        self.begin = begin;
+        self.duration = duration;
+        self.aborted = false;
+        self.nodes = 0;
+        self.killers.iter_mut().for_each(|killers| *killers = [None, None]);
+        self.history.values_mut().for_each(|score| *score /= 8);
+
+        let mut result: Option<SearchResult<A>> = None;
+        for depth in 1..=self.max_depth.min(MAX_DEPTH) {
+            let (score, best) = self.search_root(state, depth);
+            if let Some(best) = best {
+                // a partially searched iteration still knows a move at least as
+                // good as the previous best, since that one is searched first
+                result = Some(SearchResult {
+                    best,
+                    score,
+                    depth,
+                    nodes: self.nodes,
+                });
+            }
+            if self.aborted || score.abs() >= WIN - MAX_DEPTH as i32 {
+                break;
+            }
+        }
+        if let Some(result) = &result {
+            eprintln!("depth {} score {} nodes {}", result.depth, result.score, result.nodes);
+        }
+        result
+    }
+
+    fn search_root(&mut self, state: &S, depth: usize) -> (i32, Option<A>) {
+        let tt_move = self.tt.probe(state.key()).and_then(|entry| entry.best.clone());
+        let actions = self.order_actions(state.possible_actions(), tt_move, 0);
+
+        let mut alpha = -INFINITY;
+        let mut best = None;
+        for action in actions {
+            let child = state.perform_action_copy(&action);
+            let score = -self.negamax(&child, depth - 1, 1, -INFINITY, -alpha);
+            if self.aborted {
+                break;
+            }
+            if score > alpha {
+                alpha = score;
+                best = Some(action);
+            }
+        }
+
+        if !self.aborted {
+            self.tt.store(Entry {
+                key: state.key(),
+                depth: depth as u8,
+                score: alpha,
+                bound: Bound::Exact,
+                best: best.clone(),
+            });
+        }
+        (alpha, best)
+    }
+
+    fn negamax(&mut self, state: &S, depth: usize, ply: usize, mut alpha: i32, mut beta: i32) -> i32 {
+        self.nodes += 1;
+        if self.nodes & 1023 == 0 && self.begin.elapsed() >= self.duration {
+            self.aborted = true;
+        }
+        if self.aborted {
+            return 0;
+        }
+
+        if !state.playable() {
+            return terminal_score(state, ply);
+        }
+        if depth == 0 || ply >= MAX_DEPTH {
+            return self.evaluator.evaluate(state);
+        }
+
+        let key = state.key();
+        let original_alpha = alpha;
+        let mut tt_move = None;
+        if let Some(entry) = self.tt.probe(key) {
+            tt_move = entry.best.clone();
+            if entry.depth as usize >= depth {
+                let score = score_from_tt(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower => alpha = alpha.max(score),
+                    Bound::Upper => beta = beta.min(score),
+                }
+                if alpha >= beta {
+                    return score;
+                }
+            }
+        }
+
+        let actions = self.order_actions(state.possible_actions(), tt_move, ply);
+
+        let mut best_score = -INFINITY;
+        let mut best = None;
+        for action in actions {
+            let child = state.perform_action_copy(&action);
+            let score = -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha);
+            if self.aborted {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best = Some(action.clone());
+            }
+            if score > alpha {
+                alpha = score;
+            }
+            if alpha >= beta {
+                self.store_cutoff(action, depth, ply);
+                break;
+            }
+        }
+
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.tt.store(Entry {
+            key,
+            depth: depth as u8,
+            score: score_to_tt(best_score, ply),
+            bound,
+            best,
+        });
+
+        best_score
+    }
+
+    fn store_cutoff(&mut self, action: A, depth: usize, ply: usize) {
+        *self.history.entry(action.clone()).or_insert(0) += (depth * depth) as i32;
+
+        let killers = &mut self.killers[ply];
+        if killers[0].as_ref() != Some(&action) {
+            killers[1] = killers[0].take();
+            killers[0] = Some(action);
+        }
+    }
+
+    fn order_actions(&self, actions: Vec<A>, tt_move: Option<A>, ply: usize) -> Vec<A> {
+        let killers = &self.killers[ply.min(MAX_DEPTH)];
+        let mut scored = actions
+            .into_iter()
+            .map(|action| {
+                let score = if tt_move.as_ref() == Some(&action) {
+                    1 << 30
+                } else if killers[0].as_ref() == Some(&action) {
+                    1 << 29
+                } else if killers[1].as_ref() == Some(&action) {
+                    1 << 28
+                } else {
+                    *self.history.get(&action).unwrap_or(&0)
+                };
+                (score, action)
+            })
+            .collect::<Vec<_>>();
+        scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+        scored.into_iter().map(|(_, action)| action).collect()
+    }
+}
+
+fn terminal_score<P, S, R, A>(state: &S, ply: usize) -> i32
+where
+    P: GamePlayer<R>,
+    S: GameState<P, R, A>,
+    R: GameResult,
+    A: GameAction,
+{
+    let reward = state.next_player().reward(&state.outcome());
+    if reward > 0.5 {
+        WIN - ply as i32
+    } else if reward < 0.5 {
+        -(WIN - ply as i32)
+    } else {
+        0
+    }
+}
+
+// Win scores are stored relative to the node so they stay valid when the same
+// position is reached at a different ply.
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= WIN - MAX_DEPTH as i32 {
+        score + ply as i32
+    } else if score <= -(WIN - MAX_DEPTH as i32) {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= WIN - MAX_DEPTH as i32 {
+        score - ply as i32
+    } else if score <= -(WIN - MAX_DEPTH as i32) {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game::evaluation::UtttEvaluator;
+    use super::super::game::game_action::Action;
+    use super::super::game::game_state::State;
+    use super::super::game::test_util::random_game;
+    use super::*;
+
+    // Plain negamax without pruning or tables, the same scores as the search
+    // is expected to return.
+    fn minimax(state: &State, depth: usize, ply: usize) -> i32 {
+        if !state.playable() {
+            return terminal_score(state, ply);
+        }
+        if depth == 0 {
+            return UtttEvaluator.evaluate(state);
+        }
+        state
+            .possible_actions()
+            .iter()
+            .map(|action| -minimax(&state.perform_action_copy(action), depth - 1, ply + 1))
+            .max()
+            .unwrap()
+    }
+
+    // The last positions of random games where the player to move wins
+    // within three plies whatever the opponent does.
+    fn forced_wins() -> Vec<(State, i32)> {
+        let mut positions = Vec::new();
+        for seed in 0..40 {
+            let moves = random_game(seed, &State::default());
+            let mut state = State::default();
+            for (ply, action) in moves.iter().enumerate() {
+                if ply + 4 >= moves.len() {
+                    let score = minimax(&state, 3, 0);
+                    if score >= WIN - 3 {
+                        positions.push((state.clone(), score));
+                    }
+                }
+                state = state.perform_action_copy(action);
+            }
+        }
+        positions
+    }
+
+    fn search(state: &State, max_depth: usize) -> SearchResult<Action> {
+        let mut search = AlphaBeta::new(UtttEvaluator);
+        search.max_depth = max_depth;
+        search.search(state, Instant::now(), time::Duration::from_secs(60)).unwrap()
+    }
+
+    #[test]
+    fn finds_forced_wins() {
+        let positions = forced_wins();
+        assert!(positions.iter().any(|&(_, score)| score == WIN - 1));
+        assert!(positions.iter().any(|&(_, score)| score == WIN - 3));
+        for (state, score) in positions {
+            let result = search(&state, 8);
+            assert_eq!(result.score, score, "{}", state);
+            let child = state.perform_action_copy(&result.best);
+            assert_eq!(-minimax(&child, 2, 1), score, "{} {}", state, result.best);
+        }
+    }
+
+    #[test]
+    fn depth_limited_search_matches_minimax() {
+        let state = State::default();
+        let result = search(&state, 4);
+        assert_eq!(result.depth, 4);
+        assert_eq!(result.score, minimax(&state, 4, 0));
+        let child = state.perform_action_copy(&result.best);
+        assert_eq!(-minimax(&child, 3, 1), result.score);
+    }
+}
@@ -0,0 +1,16 @@
+use super::mcts::traits::*;
+
+pub trait Evaluator<P, S, R, A>
+where
+    P: GamePlayer<R>,
+    S: GameState<P, R, A>,
+    R: GameResult,
+    A: GameAction,
+{
+    // Heuristic value of a non-terminal state for the player about to move.
+    fn evaluate(&self, state: &S) -> i32;
+}
+
+pub trait PositionKey {
+    fn key(&self) -> u64;
+}
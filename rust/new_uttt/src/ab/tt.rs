@@ -0,0 +1,127 @@
+use super::mcts::traits::GameAction;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone)]
+pub struct Entry<A: GameAction> {
+    pub key: u64,
+    pub depth: u8,
+    pub score: i32,
+    pub bound: Bound,
+    pub best: Option<A>,
+}
+
+pub struct TranspositionTable<A: GameAction> {
+    entries: Vec<Option<Entry<A>>>,
+    mask: usize,
+}
+
+impl<A: GameAction> TranspositionTable<A> {
+    // `size` is rounded up to the next power of two.
+    pub fn new(size: usize) -> Self {
+        let size = size.max(1).next_power_of_two();
+        TranspositionTable {
+            entries: vec![None; size],
+            mask: size - 1,
+        }
+    }
+
+    //#[inline]
+    pub fn probe(&self, key: u64) -> Option<&Entry<A>> {
+        match &self.entries[key as usize & self.mask] {
+            Some(entry) if entry.key == key => Some(entry),
+            _ => None,
+        }
+    }
+
+    //#[inline]
+    pub fn store(&mut self, entry: Entry<A>) {
+        let slot = &mut self.entries[entry.key as usize & self.mask];
+        let replace = match slot {
+            Some(old) => old.key != entry.key || old.depth <= entry.depth,
+            None => true,
+        };
+        if replace {
+            *slot = Some(entry);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::super::game::evaluation::UtttEvaluator;
+    use super::super::game::game_action::Action;
+    use super::super::game::game_state::State;
+    use super::super::game::test_util::random_game;
+    use super::super::search::AlphaBeta;
+    use super::super::mcts::traits::GameState;
+    use super::super::traits::PositionKey;
+    use super::*;
+
+    fn entry(key: u64, depth: u8, score: i32) -> Entry<Action> {
+        Entry {
+            key,
+            depth,
+            score,
+            bound: Bound::Exact,
+            best: None,
+        }
+    }
+
+    #[test]
+    fn keeps_the_deeper_entry_of_a_position() {
+        let mut tt = TranspositionTable::<Action>::new(16);
+        tt.store(entry(5, 3, 10));
+        tt.store(entry(5, 2, 20));
+        assert_eq!(tt.probe(5).unwrap().score, 10);
+        tt.store(entry(5, 3, 30));
+        assert_eq!(tt.probe(5).unwrap().score, 30);
+    }
+
+    #[test]
+    fn another_position_takes_the_slot() {
+        let mut tt = TranspositionTable::<Action>::new(16);
+        tt.store(entry(5, 3, 10));
+        tt.store(entry(5 + 16, 1, 20));
+        assert!(tt.probe(5).is_none());
+        assert_eq!(tt.probe(5 + 16).unwrap().score, 20);
+        tt.clear();
+        assert!(tt.probe(5 + 16).is_none());
+    }
+
+    // A second search of the same position starts from the entries of the
+    // first one and has to come to the same result.
+    #[test]
+    fn warm_search_matches_a_cold_one() {
+        let mut positions = vec![State::default()];
+        for seed in 0..10 {
+            let moves = random_game(seed, &State::default());
+            let mut state = State::default();
+            for action in &moves[..moves.len() - 6] {
+                state = state.perform_action_copy(action);
+            }
+            positions.push(state);
+        }
+
+        for state in positions {
+            let mut search = AlphaBeta::new(UtttEvaluator);
+            search.max_depth = 3;
+            let cold = search.search(&state, Instant::now(), Duration::from_secs(60)).unwrap();
+            assert!(search.tt.probe(state.key()).is_some());
+            let warm = search.search(&state, Instant::now(), Duration::from_secs(60)).unwrap();
+            assert_eq!(warm.score, cold.score, "{}", state);
+            assert_eq!(warm.best, cold.best, "{}", state);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::Instant;
+
+use super::ab::search::AlphaBeta;
+use super::cg_rand::Rng;
+use super::game::evaluation::UtttEvaluator;
+use super::game::game_action::Action;
+use super::game::game_state::{State, UTTTResult};
+use super::game::player::Player;
+use super::mcts::tree::{MctsTree, RootStrategy};
+
+// An engine that plays games in process, see `play_match`.
+pub trait Contestant {
+    fn name(&self) -> String;
+
+    // Called before every game with the position it starts from.
+    fn new_game(&mut self, start: &State);
+
+    // Our move in `state`, due before `deadline`.
+    fn choose(&mut self, state: &State, deadline: Instant, rng: &mut Box<Rng>) -> Action;
+
+    // Every move of the game once it is played, ours included.
+    fn observe(&mut self, _action: Action) {}
+}
+
+// MCTS keeping its tree between moves, searching until the deadline.
+pub struct MctsBot {
+    pub root_strategy: RootStrategy,
+    pub early_stop: bool,
+
+    tree: MctsTree<Player, State, UTTTResult, Action>,
+}
+
+impl MctsBot {
+    pub fn new(root_strategy: RootStrategy) -> Self {
+        MctsBot {
+            root_strategy,
+            early_stop: false,
+            tree: MctsTree::new(State::default()),
+        }
+    }
+}
+
+impl Contestant for MctsBot {
+    fn name(&self) -> String {
+        match self.root_strategy {
+            RootStrategy::Uct => "uct",
+            RootStrategy::SequentialHalving { gumbel: None } => "halving",
+            RootStrategy::SequentialHalving { gumbel: Some(_) } => "gumbel",
+        }
+        .to_string()
+    }
+
+    fn new_game(&mut self, start: &State) {
+        self.tree = MctsTree::new(start.clone());
+        self.tree.root_strategy = self.root_strategy;
+        self.tree.early_stop = self.early_stop;
+    }
+
+    fn choose(&mut self, state: &State, deadline: Instant, rng: &mut Box<Rng>) -> Action {
+        let begin = Instant::now();
+        self.tree.expand_tree(begin, deadline.saturating_duration_since(begin), rng);
+        if self.tree.best_action().is_none() {
+            // no time for a single iteration
+            return state.random_move(rng).unwrap();
+        }
+        self.tree.best_child().state.last_action.unwrap()
+    }
+
+    fn observe(&mut self, action: Action) {
+        self.tree.move_down(action);
+        self.tree.collect_garbage();
+    }
+}
+
+pub struct RandomBot;
+
+impl Contestant for RandomBot {
+    fn name(&self) -> String {
+        "random".to_string()
+    }
+
+    fn new_game(&mut self, _start: &State) {}
+
+    fn choose(&mut self, state: &State, _deadline: Instant, rng: &mut Box<Rng>) -> Action {
+        state.random_move(rng).unwrap()
+    }
+}
+
+// Iterative deepening alpha-beta with the hand written evaluation.
+pub struct AlphaBetaBot {
+    search: AlphaBeta<Player, State, UTTTResult, Action, UtttEvaluator>,
+}
+
+impl AlphaBetaBot {
+    pub fn new(max_depth: usize) -> Self {
+        let mut search = AlphaBeta::new(UtttEvaluator);
+        search.max_depth = max_depth;
+        AlphaBetaBot { search }
+    }
+}
+
+impl Contestant for AlphaBetaBot {
+    fn name(&self) -> String {
+        format!("alphabeta-{}", self.search.max_depth)
+    }
+
+    fn new_game(&mut self, _start: &State) {
+        self.search.tt.clear();
+    }
+
+    fn choose(&mut self, state: &State, deadline: Instant, rng: &mut Box<Rng>) -> Action {
+        let begin = Instant::now();
+        match self.search.search(state, begin, deadline.saturating_duration_since(begin)) {
+            Some(result) => result.best,
+            None => state.random_move(rng).unwrap(),
+        }
+    }
+}
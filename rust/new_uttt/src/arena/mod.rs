@@ -0,0 +1,4 @@
+pub mod bot;
+pub mod play;
+
+pub use super::*;
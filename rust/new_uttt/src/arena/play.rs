@@ -0,0 +1,237 @@
+use std::time::{Duration, Instant};
+
+use super::cg_rand::Rng;
+use super::game::game_state::State;
+use super::game::record::{GameRecord, RecordResult};
+use super::mcts::traits::GameState;
+
+use super::bot::Contestant;
+
+#[derive(Clone)]
+pub struct MatchConfig {
+    // colours alternate between games
+    pub games: usize,
+    pub first_turn: Duration,
+    pub turn: Duration,
+    // a move taking longer than its limit plus `grace` loses the game, with
+    // `None` the limits are only deadlines for the bots
+    pub grace: Option<Duration>,
+    // start positions, each one played twice in a row with colours swapped
+    pub openings: Vec<State>,
+    // random when `None`
+    pub seed: Option<u64>,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        MatchConfig {
+            games: 2,
+            first_turn: Duration::from_millis(1000),
+            turn: Duration::from_millis(100),
+            grace: Some(Duration::from_millis(5)),
+            openings: Vec::new(),
+            seed: None,
+        }
+    }
+}
+
+pub struct MatchResult {
+    // from the point of view of the first engine
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+    pub records: Vec<GameRecord>,
+}
+
+// Plays `engine_a` against `engine_b` in this thread, `engine_a` moving
+// first in the even games.
+pub fn play_match<A, B>(engine_a: &mut A, engine_b: &mut B, config: &MatchConfig) -> MatchResult
+where
+    A: Contestant,
+    B: Contestant,
+{
+    let mut rng: Box<Rng> = Box::new(config.seed.map_or_else(Rng::new, Rng::with_seed));
+    let mut result = MatchResult {
+        wins: 0,
+        draws: 0,
+        losses: 0,
+        records: Vec::with_capacity(config.games),
+    };
+
+    for game in 0..config.games {
+        let start = match config.openings.len() {
+            0 => None,
+            count => Some(&config.openings[game / 2 % count]),
+        };
+        let a_first = game % 2 == 0;
+        let record = if a_first {
+            play_game([&mut *engine_a, &mut *engine_b], start, config, &mut rng)
+        } else {
+            play_game([&mut *engine_b, &mut *engine_a], start, config, &mut rng)
+        };
+
+        match (record.result, a_first) {
+            (RecordResult::FirstWon, true) | (RecordResult::SecondWon, false) => result.wins += 1,
+            (RecordResult::FirstWon, false) | (RecordResult::SecondWon, true) => result.losses += 1,
+            _ => result.draws += 1,
+        }
+        result.records.push(record);
+    }
+    result
+}
+
+// One game, `bots[0]` moving first. A bot that runs out of time or plays an
+// illegal move loses at once.
+pub fn play_game(
+    mut bots: [&mut dyn Contestant; 2],
+    start: Option<&State>,
+    config: &MatchConfig,
+    rng: &mut Box<Rng>,
+) -> GameRecord {
+    let mut record = GameRecord::new(&bots[0].name(), &bots[1].name());
+    record.start = start.cloned();
+    record.seeds.push(rng.get_seed());
+
+    let mut state = record.start_state();
+    for bot in bots.iter_mut() {
+        bot.new_game(&state);
+    }
+    let mut turns = [0, 0];
+    let mut to_move = 0;
+    while state.playable() {
+        let limit = if turns[to_move] == 0 { config.first_turn } else { config.turn };
+        let begin = Instant::now();
+        let action = bots[to_move].choose(&state, begin + limit, rng);
+        let time = begin.elapsed();
+
+        let late = config.grace.is_some_and(|grace| time > limit + grace);
+        if late || !state.possible_actions().contains(&action) {
+            let reason = if late { format!("timeout ({:?})", time) } else { format!("illegal move {}", action) };
+            eprintln!("{} loses by {}", bots[to_move].name(), reason);
+            record.result = if to_move == 0 {
+                RecordResult::SecondWon
+            } else {
+                RecordResult::FirstWon
+            };
+            return record;
+        }
+
+        record.push(action, Some(time));
+        state = state.perform_action_copy(&action);
+        for bot in bots.iter_mut() {
+            bot.observe(action);
+        }
+        turns[to_move] += 1;
+        to_move = 1 - to_move;
+    }
+
+    record.finish(state.outcome());
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game::game_action::Action;
+    use super::*;
+
+    // Plays the first or the last legal action, counting the moves it sees.
+    struct Scripted {
+        last: bool,
+        observed: usize,
+    }
+
+    impl Scripted {
+        fn new(last: bool) -> Self {
+            Scripted { last, observed: 0 }
+        }
+    }
+
+    impl Contestant for Scripted {
+        fn name(&self) -> String {
+            if self.last { "last" } else { "first" }.to_string()
+        }
+
+        fn new_game(&mut self, _start: &State) {
+            self.observed = 0;
+        }
+
+        fn choose(&mut self, state: &State, _deadline: Instant, _rng: &mut Box<Rng>) -> Action {
+            let actions = state.possible_actions();
+            if self.last { actions[actions.len() - 1] } else { actions[0] }
+        }
+
+        fn observe(&mut self, _action: Action) {
+            self.observed += 1;
+        }
+    }
+
+    fn config() -> MatchConfig {
+        let opening = State::default().perform_action_copy(&Action::from_row_col(4, 4).unwrap());
+        MatchConfig {
+            games: 4,
+            grace: None,
+            openings: vec![State::default(), opening],
+            seed: Some(1),
+            ..MatchConfig::default()
+        }
+    }
+
+    fn moves(record: &GameRecord) -> Vec<Action> {
+        record.moves.iter().map(|recorded| recorded.action).collect()
+    }
+
+    #[test]
+    fn match_records_replay() {
+        let (mut first, mut last) = (Scripted::new(false), Scripted::new(true));
+        let result = play_match(&mut first, &mut last, &config());
+        assert_eq!(result.wins + result.draws + result.losses, 4);
+        assert_eq!(result.records.len(), 4);
+
+        for (game, record) in result.records.iter().enumerate() {
+            let names = if game % 2 == 0 { ["first", "last"] } else { ["last", "first"] };
+            assert_eq!([record.first.as_str(), record.second.as_str()], names);
+            let opening = &config().openings[game / 2];
+            assert_eq!(record.start_state().to_string(), opening.to_string());
+
+            let states = record.replay().unwrap();
+            let end = states.last().unwrap();
+            assert!(!end.playable());
+            let first_mover = record.start_state().next_player();
+            assert_eq!(record.result, RecordResult::from_outcome(end.outcome(), first_mover));
+            assert_eq!(record.to_string().parse::<GameRecord>().unwrap().to_string(), record.to_string());
+        }
+        assert_eq!(first.observed, result.records[3].moves.len());
+
+        // the same contestants play the same games
+        let again = play_match(&mut Scripted::new(false), &mut Scripted::new(true), &config());
+        for (record, again) in result.records.iter().zip(&again.records) {
+            assert_eq!(moves(record), moves(again));
+            assert_eq!(record.result, again.result);
+        }
+    }
+
+    struct Illegal;
+
+    impl Contestant for Illegal {
+        fn name(&self) -> String {
+            "illegal".to_string()
+        }
+
+        fn new_game(&mut self, _start: &State) {}
+
+        fn choose(&mut self, _state: &State, _deadline: Instant, _rng: &mut Box<Rng>) -> Action {
+            Action::from_row_col(4, 4).unwrap()
+        }
+    }
+
+    #[test]
+    fn illegal_move_loses() {
+        let mut config = config();
+        config.games = 2;
+        config.openings = vec![State::default().perform_action_copy(&Action::from_row_col(4, 4).unwrap())];
+        let result = play_match(&mut Illegal, &mut Scripted::new(false), &config);
+        assert_eq!((result.wins, result.draws, result.losses), (0, 0, 2));
+        assert_eq!(result.records[0].result, RecordResult::SecondWon);
+        assert_eq!(result.records[1].result, RecordResult::FirstWon);
+    }
+}
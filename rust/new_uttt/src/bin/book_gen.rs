@@ -0,0 +1,226 @@
+extern crate new_uttt;
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time;
+
+use new_uttt::book::opening_book::{BookMove, OpeningBook};
+use new_uttt::cg_rand::Rng;
+use new_uttt::game::game_state::State;
+use new_uttt::mcts::traits::GameState;
+use new_uttt::mcts::tree::MctsTree;
+
+// moves kept per book position
+const BOOK_MOVES: usize = 4;
+const USAGE: &str = "usage: book_gen <book> [depth] [iterations] [width] [threads]";
+
+struct Settings {
+    path: PathBuf,
+    depth: usize,
+    iterations: u32,
+    width: usize,
+}
+
+// Fills an opening book with long searches, starting from the empty board and
+// following the `width` most visited moves of every position down to `depth`
+// plies. The book is written after every position, and positions already in
+// it are not searched again, so an interrupted run picks up where it stopped.
+//
+// book_gen <book> [depth] [iterations] [width] [threads]
+fn main() {
+    let args = std::env::args().collect::<Vec<_>>();
+    let path = match args.get(1) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            eprintln!("{}", USAGE);
+            return;
+        }
+    };
+    // a search without iterations has no moves to put in the book
+    let iterations = match args.get(3).map(|iterations| iterations.parse::<u32>()) {
+        None => 2_000_000,
+        Some(Ok(iterations)) if iterations > 0 => iterations,
+        Some(_) => {
+            eprintln!("iterations must be a positive number");
+            eprintln!("{}", USAGE);
+            return;
+        }
+    };
+    let settings = Arc::new(Settings {
+        path,
+        depth: args.get(2).and_then(|depth| depth.parse().ok()).unwrap_or(4),
+        iterations,
+        width: args.get(4).and_then(|width| width.parse().ok()).unwrap_or(2),
+    });
+    let threads = args
+        .get(5)
+        .and_then(|threads| threads.parse().ok())
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |threads| threads.get()));
+
+    let book = match load(&settings.path) {
+        Ok(book) => book,
+        Err(error) => {
+            eprintln!("cannot load {}: {}", settings.path.display(), error);
+            return;
+        }
+    };
+    eprintln!("{} positions in the book", book.len());
+    let book = Arc::new(Mutex::new(book));
+
+    let mut seen = HashSet::new();
+    let mut frontier = vec![State::default()];
+    for depth in 0..=settings.depth {
+        frontier.retain(|state| state.playable() && seen.insert(state.canonical_hash()));
+        eprintln!("depth {}: {} positions", depth, frontier.len());
+        if frontier.is_empty() {
+            break;
+        }
+
+        let queue = Arc::new(Mutex::new(frontier));
+        let next = Arc::new(Mutex::new(Vec::new()));
+        let workers = (0..threads)
+            .map(|worker| {
+                let (queue, next, book, settings) = (
+                    Arc::clone(&queue),
+                    Arc::clone(&next),
+                    Arc::clone(&book),
+                    Arc::clone(&settings),
+                );
+                thread::spawn(move || work(worker as u64, &queue, &next, &book, &settings))
+            })
+            .collect::<Vec<_>>();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        frontier = std::mem::take(&mut *next.lock().unwrap());
+    }
+    eprintln!("{} positions in the book", book.lock().unwrap().len());
+}
+
+fn work(
+    worker: u64,
+    queue: &Mutex<Vec<State>>,
+    next: &Mutex<Vec<State>>,
+    book: &Mutex<OpeningBook>,
+    settings: &Settings,
+) {
+    let mut rng = Box::new(Rng::with_seed(worker + 1));
+    loop {
+        let state = match queue.lock().unwrap().pop() {
+            Some(state) => state,
+            None => return,
+        };
+
+        let known = book.lock().unwrap().moves(&state);
+        let moves = if known.is_empty() {
+            let moves = search(&state, settings.iterations, &mut rng);
+            if moves.is_empty() {
+                eprintln!("{} has no searched moves, skipping it", state);
+                continue;
+            }
+            let mut book = book.lock().unwrap();
+            book.insert(&state, &moves);
+            if let Err(error) = save(&book, &settings.path) {
+                eprintln!("cannot write {}: {}", settings.path.display(), error);
+            }
+            moves
+        } else {
+            known
+        };
+
+        let mut next = next.lock().unwrap();
+        for book_move in moves.iter().take(settings.width) {
+            next.push(state.perform_action_copy(&book_move.action));
+        }
+    }
+}
+
+// The most visited moves of a fresh search, best first.
+fn search(state: &State, iterations: u32, rng: &mut Box<Rng>) -> Vec<BookMove> {
+    let begin = time::Instant::now();
+    let mcts = MctsTree::new(state.clone());
+    mcts.collapse_root(&state.equivalent_actions());
+    mcts.expand_iterations(iterations, rng);
+
+    let root = mcts.root.borrow();
+    let mut moves = root
+        .children
+        .borrow()
+        .iter()
+        .map(|(&action, child)| BookMove {
+            action,
+            visits: child.visits.get() as u32,
+            score: child.wins.get() / child.visits.get(),
+        })
+        .collect::<Vec<_>>();
+    moves.sort_by_key(|book_move| std::cmp::Reverse(book_move.visits));
+    moves.truncate(BOOK_MOVES);
+
+    if let Some(best) = moves.first() {
+        eprintln!("{} -> {} ({:.3}) in {:?}", state, best.action, best.score, begin.elapsed());
+    }
+    moves
+}
+
+// A missing file is an empty book, so the first run needs no setup.
+fn load(path: &Path) -> Result<OpeningBook, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => OpeningBook::decode(&text).map_err(|error| error.to_string()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(OpeningBook::new()),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+// Written next to the book first, an interrupted write leaves the old book.
+fn save(book: &OpeningBook, path: &Path) -> std::io::Result<()> {
+    let partial = path.with_extension("partial");
+    fs::write(&partial, book.encode())?;
+    fs::rename(&partial, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use new_uttt::game::game_action::Action;
+    use new_uttt::game::symmetry::Symmetry;
+
+    use super::*;
+
+    // Moves searched for one position are found again, turned the same way,
+    // from every rotation and reflection of it, also after a save and load.
+    #[test]
+    fn searched_moves_serve_symmetric_positions() {
+        let moves = [(0, 1), (1, 3), (3, 2)].map(|(row, col)| Action::from_row_col(row, col).unwrap());
+        let after = |symmetry: Symmetry| {
+            moves
+                .iter()
+                .fold(State::default(), |state, &action| state.perform_action_copy(&symmetry.action(action)))
+        };
+        let state = after(Symmetry::IDENTITY);
+        let mut rng = Box::new(Rng::with_seed(1));
+        let searched = search(&state, 2000, &mut rng);
+        assert!(!searched.is_empty());
+
+        let mut book = OpeningBook::new();
+        book.insert(&state, &searched);
+        let path = std::env::temp_dir().join(format!("book_gen_test_{}.txt", std::process::id()));
+        save(&book, &path).unwrap();
+        let book = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        for symmetry in Symmetry::ALL {
+            let image = after(symmetry);
+            let found = book.moves(&image);
+            assert_eq!(found.len(), searched.len());
+            for (found, searched) in found.iter().zip(&searched) {
+                // a symmetric image only matches up to a symmetry keeping it
+                let expected = symmetry.action(searched.action);
+                assert!(image.symmetries().iter().any(|keeping| keeping.action(found.action) == expected));
+                assert_eq!(found.visits, searched.visits);
+            }
+        }
+    }
+}
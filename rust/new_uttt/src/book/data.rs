@@ -0,0 +1,3 @@
+// A single entry written by hand and encoded with `OpeningBook::encode`: the
+// center on the empty board, the opening the bot played before it had a book.
+pub const BOOK: &str = "AQAAAAAAAAAAASgBAAAAAIA";
@@ -0,0 +1,69 @@
+// Base64 with the standard alphabet and no padding, so a book fits in a
+// string literal of the single file submission.
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0_u32, |bits, (i, &byte)| bits | (byte as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    encoded
+}
+
+// `None` on characters outside the alphabet. Whitespace is skipped so long
+// books can be wrapped.
+pub fn decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits = 0_u32;
+    let mut count = 0;
+    for byte in text.bytes().filter(|byte| !byte.is_ascii_whitespace() && *byte != b'=') {
+        let value = ALPHABET.iter().position(|&symbol| symbol == byte)? as u32;
+        bits = bits << 6 | value;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            bytes.push((bits >> count) as u8);
+            bits &= (1 << count) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_values() {
+        assert_eq!(encode(b""), "");
+        assert_eq!(encode(b"M"), "TQ");
+        assert_eq!(encode(b"Ma"), "TWE");
+        assert_eq!(encode(b"Man"), "TWFu");
+        assert_eq!(encode(&[0xfb, 0xff]), "+/8");
+    }
+
+    #[test]
+    fn round_trip() {
+        for length in 0..32 {
+            let bytes = (0..length).map(|i| (i * 37 + 11) as u8).collect::<Vec<_>>();
+            assert_eq!(decode(&encode(&bytes)), Some(bytes));
+        }
+        let every_byte = (0..=255).collect::<Vec<u8>>();
+        assert_eq!(decode(&encode(&every_byte)), Some(every_byte));
+    }
+
+    #[test]
+    fn skips_whitespace_and_padding() {
+        assert_eq!(decode("TW\nF u"), Some(b"Man".to_vec()));
+        assert_eq!(decode("TWE="), Some(b"Ma".to_vec()));
+    }
+
+    #[test]
+    fn rejects_other_characters() {
+        assert_eq!(decode("TW-u"), None);
+        assert_eq!(decode("TWFu!"), None);
+    }
+}
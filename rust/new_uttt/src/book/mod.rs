@@ -0,0 +1,5 @@
+pub mod encoding;
+pub mod opening_book;
+pub mod data;
+
+pub use super::*;
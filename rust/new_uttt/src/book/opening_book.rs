@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use super::game::game_action::Action;
+use super::game::game_state::State;
+
+use super::data::BOOK;
+use super::encoding::{decode, encode};
+
+const VERSION: u8 = 1;
+const MOVE_SIZE: usize = 7;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BookMove {
+    pub action: Action,
+    pub visits: u32,
+    // mean reward for the player making the move, in 0..=1
+    pub score: f64,
+}
+
+#[derive(Debug)]
+pub enum BookError {
+    Encoding,
+    Version(u8),
+    Truncated,
+    Action(usize),
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookError::Encoding => write!(f, "the book is not valid base64"),
+            BookError::Version(version) => write!(f, "unknown book version {}", version),
+            BookError::Truncated => write!(f, "the book ends in the middle of an entry"),
+            BookError::Action(index) => write!(f, "action index {} in the book is off the board", index),
+        }
+    }
+}
+
+impl Error for BookError {}
+
+// Moves for positions keyed by their canonical hash. Positions and moves are
+// stored in the canonical orientation, so one entry serves all the symmetric
+// positions and the moves are turned back on lookup.
+#[derive(Clone, Default)]
+pub struct OpeningBook {
+    entries: HashMap<u64, Vec<BookMove>>,
+}
+
+impl OpeningBook {
+    pub fn new() -> Self {
+        OpeningBook {
+            entries: HashMap::new(),
+        }
+    }
+
+    // The book compiled into the bot, empty if it does not decode.
+    pub fn embedded() -> Self {
+        OpeningBook::decode(BOOK).unwrap_or_else(|error| {
+            eprintln!("embedded book: {}", error);
+            OpeningBook::new()
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, state: &State) -> bool {
+        self.entries.contains_key(&state.canonical_hash())
+    }
+
+    // Replaces the moves known for `state`.
+    pub fn insert(&mut self, state: &State, moves: &[BookMove]) {
+        let (key, symmetry) = state.canonical();
+        let moves = moves
+            .iter()
+            .map(|book_move| BookMove {
+                action: symmetry.action(book_move.action),
+                ..*book_move
+            })
+            .collect();
+        self.entries.insert(key, moves);
+    }
+
+    // The moves known for `state`, oriented like it.
+    pub fn moves(&self, state: &State) -> Vec<BookMove> {
+        let (key, symmetry) = state.canonical();
+        let inverse = symmetry.inverse();
+        self.entries
+            .get(&key)
+            .map(|moves| {
+                moves
+                    .iter()
+                    .map(|book_move| BookMove {
+                        action: inverse.action(book_move.action),
+                        ..*book_move
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // The most visited book move.
+    pub fn best(&self, state: &State) -> Option<Action> {
+        self.moves(state)
+            .into_iter()
+            .max_by_key(|book_move| book_move.visits)
+            .map(|book_move| book_move.action)
+    }
+
+    // A version byte, then for every entry the key, the number of moves and
+    // per move its index, visits and score as a fraction of u16::MAX, all
+    // little endian. Entries are sorted by key so equal books encode the same.
+    pub fn encode(&self) -> String {
+        let mut keys = self.entries.keys().copied().collect::<Vec<_>>();
+        keys.sort_unstable();
+
+        let mut bytes = vec![VERSION];
+        for key in keys {
+            let moves = &self.entries[&key];
+            bytes.extend_from_slice(&key.to_le_bytes());
+            bytes.push(moves.len().min(u8::MAX as usize) as u8);
+            for book_move in moves.iter().take(u8::MAX as usize) {
+                bytes.push(book_move.action.index() as u8);
+                bytes.extend_from_slice(&book_move.visits.to_le_bytes());
+                let score = (book_move.score.clamp(0., 1.) * u16::MAX as f64).round() as u16;
+                bytes.extend_from_slice(&score.to_le_bytes());
+            }
+        }
+        encode(&bytes)
+    }
+
+    pub fn decode(text: &str) -> Result<Self, BookError> {
+        let bytes = decode(text).ok_or(BookError::Encoding)?;
+        let mut book = OpeningBook::new();
+        let (&version, mut rest) = match bytes.split_first() {
+            Some(split) => split,
+            None => return Ok(book),
+        };
+        if version != VERSION {
+            return Err(BookError::Version(version));
+        }
+
+        while !rest.is_empty() {
+            if rest.len() < 9 {
+                return Err(BookError::Truncated);
+            }
+            let key = u64::from_le_bytes(rest[..8].try_into().unwrap());
+            let count = rest[8] as usize;
+            rest = &rest[9..];
+            if rest.len() < count * MOVE_SIZE {
+                return Err(BookError::Truncated);
+            }
+
+            let mut moves = Vec::with_capacity(count);
+            for chunk in rest[..count * MOVE_SIZE].chunks(MOVE_SIZE) {
+                let index = chunk[0] as usize;
+                moves.push(BookMove {
+                    action: Action::from_index(index).map_err(|_| BookError::Action(index))?,
+                    visits: u32::from_le_bytes(chunk[1..5].try_into().unwrap()),
+                    score: u16::from_le_bytes(chunk[5..7].try_into().unwrap()) as f64 / u16::MAX as f64,
+                });
+            }
+            book.entries.insert(key, moves);
+            rest = &rest[count * MOVE_SIZE..];
+        }
+        Ok(book)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game::symmetry::Symmetry;
+    use super::super::mcts::traits::GameState;
+    use super::*;
+
+    fn book_move(row: usize, col: usize, visits: u32, score: f64) -> BookMove {
+        BookMove {
+            action: Action::from_row_col(row, col).unwrap(),
+            visits,
+            score,
+        }
+    }
+
+    fn after(moves: &[(usize, usize)]) -> State {
+        moves.iter().fold(State::default(), |state, &(row, col)| {
+            state.perform_action_copy(&Action::from_row_col(row, col).unwrap())
+        })
+    }
+
+    #[test]
+    fn symmetric_positions_share_an_entry() {
+        let state = after(&[(0, 0)]);
+        let mut book = OpeningBook::new();
+        book.insert(&state, &[book_move(1, 1, 10, 0.5), book_move(2, 0, 5, 0.25)]);
+
+        for symmetry in Symmetry::ALL {
+            let image = after(&[symmetry.action(Action::from_row_col(0, 0).unwrap()).to_row_col()]);
+            let moves = book.moves(&image);
+            // some images are the same position, the moves then only match
+            // up to a symmetry that keeps it
+            let expected = symmetry.action(Action::from_row_col(1, 1).unwrap());
+            assert_eq!(moves.len(), 2);
+            assert!(image.symmetries().iter().any(|keeping| keeping.action(moves[0].action) == expected));
+            assert_eq!(moves[0].visits, 10);
+        }
+        assert_eq!(book.len(), 1);
+        assert!(!book.contains(&after(&[(4, 4)])));
+    }
+
+    #[test]
+    fn best_is_the_most_visited() {
+        let mut book = OpeningBook::new();
+        book.insert(&State::default(), &[book_move(0, 0, 3, 0.9), book_move(4, 4, 7, 0.4)]);
+        assert_eq!(book.best(&State::default()), Action::from_row_col(4, 4).ok());
+        assert_eq!(book.best(&after(&[(4, 4)])), None);
+    }
+
+    #[test]
+    fn encoding_round_trip() {
+        let mut book = OpeningBook::new();
+        book.insert(&State::default(), &[book_move(4, 4, 1_000_000, 0.55)]);
+        book.insert(&after(&[(4, 4)]), &[book_move(3, 3, 40, 0.5), book_move(4, 3, 30, 1.)]);
+        book.insert(&after(&[(4, 4), (3, 3)]), &[book_move(0, 0, 2, 0.)]);
+
+        let text = book.encode();
+        let decoded = OpeningBook::decode(&text).unwrap();
+        assert_eq!(decoded.len(), book.len());
+        assert_eq!(decoded.encode(), text);
+        for state in [State::default(), after(&[(4, 4)]), after(&[(4, 4), (3, 3)])] {
+            for (decoded, original) in decoded.moves(&state).iter().zip(book.moves(&state)) {
+                assert_eq!(decoded.action, original.action);
+                assert_eq!(decoded.visits, original.visits);
+                // scores are stored in 16 bits
+                assert!((decoded.score - original.score).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn embedded_book_opens_in_the_center() {
+        let book = OpeningBook::embedded();
+        assert_eq!(book.best(&State::default()), Action::from_index(40).ok());
+    }
+
+    #[test]
+    fn rejects_broken_books() {
+        assert!(OpeningBook::decode("").unwrap().is_empty());
+        assert!(matches!(OpeningBook::decode("!"), Err(BookError::Encoding)));
+        assert!(matches!(OpeningBook::decode(&encode(&[VERSION + 1])), Err(BookError::Version(_))));
+        let text = OpeningBook::embedded().encode();
+        let bytes = decode(&text).unwrap();
+        assert!(matches!(OpeningBook::decode(&encode(&bytes[..bytes.len() - 1])), Err(BookError::Truncated)));
+        let mut off_board = bytes.clone();
+        off_board[10] = 81;
+        assert!(matches!(OpeningBook::decode(&encode(&off_board)), Err(BookError::Action(81))));
+    }
+}
@@ -0,0 +1,241 @@
+use std::io::{self, BufRead, BufReader};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::TryRecvError;
+use std::time::{Duration, Instant};
+
+use super::input::{InputError, InputReader};
+use super::reader::BackgroundReader;
+use super::safety::Responder;
+use super::time_manager::{Phase, TimeManager};
+
+// The game specific part of a Codingame bot. The `Driver` reads the input,
+// keeps the clock and makes sure every turn gets exactly one answer.
+pub trait Bot {
+    // input given once before the first turn
+    type Init;
+    type Input: Send + 'static;
+    type Output: Copy + Send + 'static;
+
+    fn read_init<B: BufRead>(input: &mut InputReader<B>) -> Result<Self::Init, InputError>;
+    fn read_turn<B: BufRead>(input: &mut InputReader<B>) -> Result<Self::Input, InputError>;
+    fn write(output: &Self::Output);
+
+    // The responder takes progress updates of the fallback answer.
+    fn init(&mut self, init: Self::Init, responder: &Responder<Self::Output>);
+
+    // Brings the bot up to date with the input of a turn, before its clock is
+    // set. The phase weighs the time budget of the turn.
+    fn observe(&mut self, input: &Self::Input) -> Phase;
+
+    // An answer available at once, written instead of the one from `turn`
+    // when that one is late or panics.
+    fn fallback(&mut self, input: &Self::Input) -> Self::Output;
+
+    // A legal answer taken from the input alone, for when even `fallback`
+    // panics.
+    fn first_legal(input: &Self::Input) -> Self::Output;
+
+    fn turn(&mut self, input: Self::Input, deadline: Instant) -> Self::Output;
+
+    // The answer that was actually written this turn.
+    fn answered(&mut self, output: Self::Output);
+
+    // Called when `observe`, `fallback` or `turn` panicked, to get back to a
+    // usable state.
+    fn recover(&mut self) {}
+
+    // Called while waiting for the next input, returns once `stop` is true or
+    // when there is nothing left to do.
+    fn ponder<F: FnMut() -> bool>(&mut self, _stop: F) {}
+}
+
+pub struct Driver {
+    pub time_manager: TimeManager,
+    // the watchdog answers this long before the referee limit
+    pub watchdog_margin: Duration,
+}
+
+impl Driver {
+    pub fn new(time_manager: TimeManager, watchdog_margin: Duration) -> Self {
+        Driver {
+            time_manager,
+            watchdog_margin,
+        }
+    }
+
+    // Plays until the end of stdin.
+    pub fn run<B: Bot>(&mut self, bot: &mut B) {
+        // a locked stdin can't move to the reader thread
+        self.run_with(bot, BufReader::new(io::stdin()));
+    }
+
+    // Plays until the end of `input`.
+    pub fn run_with<B: Bot, I: BufRead + Send + 'static>(&mut self, bot: &mut B, input: I) {
+        let mut input = InputReader::new(input);
+        let init = match B::read_init(&mut input) {
+            Ok(init) => init,
+            Err(error) => {
+                eprintln!("{}", error);
+                return;
+            }
+        };
+        let reader = BackgroundReader::spawn(input, |input| B::read_turn(input));
+        let responder = Responder::spawn(B::write);
+        bot.init(init, &responder);
+
+        loop {
+            let received = if self.time_manager.turn() == 0 {
+                reader.recv()
+            } else {
+                let mut received = Err(TryRecvError::Empty);
+                bot.ponder(|| {
+                    received = reader.try_recv();
+                    !matches!(received, Err(TryRecvError::Empty))
+                });
+                match received {
+                    Err(TryRecvError::Empty) => reader.recv(),
+                    received => received.ok(),
+                }
+            };
+            let (begin, input) = match received {
+                Some(received) => received,
+                None => return,
+            };
+
+            // with RUST_BACKTRACE set the default hook holds stderr for long
+            // enough to matter, a turn only reports the panic itself
+            let default_hook = panic::take_hook();
+            panic::set_hook(Box::new(|info| eprintln!("{}", info)));
+
+            // a bot that could not follow the input only plays the fallback
+            let turn = self.time_manager.turn();
+            let phase = guarded(bot, turn, "observe", |bot| bot.observe(&input));
+            let budget = self.time_manager.budget(phase.unwrap_or(Phase::Middlegame));
+            let fallback = guarded(bot, turn, "fallback", |bot| bot.fallback(&input));
+            let fallback = fallback.unwrap_or_else(|| B::first_legal(&input));
+            responder.start_turn(begin + self.time_manager.limit() - self.watchdog_margin, fallback);
+
+            let decided = match phase {
+                Some(_) => guarded(bot, turn, "turn", |bot| bot.turn(input, begin + budget)),
+                None => None,
+            };
+            panic::set_hook(default_hook);
+            let output = decided.unwrap_or(fallback);
+
+            let output = responder.answer(output);
+            self.time_manager.finish_turn(begin, budget);
+            bot.answered(output);
+        }
+    }
+}
+
+// Runs `call`, and on a panic gets the bot back to a usable state.
+fn guarded<B: Bot, T>(bot: &mut B, turn: usize, name: &str, call: impl FnOnce(&mut B) -> T) -> Option<T> {
+    match panic::catch_unwind(AssertUnwindSafe(|| call(bot))) {
+        Ok(value) => Some(value),
+        Err(_) => {
+            eprintln!("{} panicked in turn {}, playing the fallback", name, turn);
+            bot.recover();
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    static WRITTEN: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+    // Gets the turn number as input, the answers tell which method made them.
+    struct PanickingBot {
+        panics: &'static [&'static str],
+        recovered: usize,
+        answered: Vec<u32>,
+    }
+
+    impl PanickingBot {
+        fn check(&self, method: &str, turn: u32) {
+            if turn == 1 && self.panics.contains(&method) {
+                panic!("{} of turn {}", method, turn);
+            }
+        }
+    }
+
+    impl Bot for PanickingBot {
+        type Init = ();
+        type Input = u32;
+        type Output = u32;
+
+        fn read_init<B: BufRead>(_input: &mut InputReader<B>) -> Result<(), InputError> {
+            Ok(())
+        }
+
+        fn read_turn<B: BufRead>(input: &mut InputReader<B>) -> Result<u32, InputError> {
+            Ok(input.read_line()?.trim().parse().unwrap())
+        }
+
+        fn write(output: &u32) {
+            WRITTEN.lock().unwrap().push(*output);
+        }
+
+        fn init(&mut self, _init: (), _responder: &Responder<u32>) {}
+
+        fn observe(&mut self, &turn: &u32) -> Phase {
+            self.check("observe", turn);
+            Phase::Opening
+        }
+
+        fn fallback(&mut self, &turn: &u32) -> u32 {
+            self.check("fallback", turn);
+            turn * 10 + 1
+        }
+
+        fn first_legal(&turn: &u32) -> u32 {
+            turn * 10 + 2
+        }
+
+        fn turn(&mut self, turn: u32, _deadline: Instant) -> u32 {
+            self.check("turn", turn);
+            turn * 10 + 3
+        }
+
+        fn answered(&mut self, output: u32) {
+            self.answered.push(output);
+        }
+
+        fn recover(&mut self) {
+            self.recovered += 1;
+        }
+    }
+
+    #[test]
+    fn panics_fall_back() {
+        let cases: [(&[&str], u32, usize); 5] = [
+            (&["observe"], 11, 1),
+            (&["fallback"], 13, 1),
+            (&["turn"], 11, 1),
+            (&["fallback", "turn"], 12, 2),
+            (&["observe", "fallback"], 12, 2),
+        ];
+        for (panics, answer, recovered) in cases {
+            WRITTEN.lock().unwrap().clear();
+            let mut bot = PanickingBot {
+                panics,
+                recovered: 0,
+                answered: Vec::new(),
+            };
+            let time_manager =
+                TimeManager::new(Duration::from_millis(1000), Duration::from_millis(100), Duration::from_millis(5));
+            let mut driver = Driver::new(time_manager, Duration::from_millis(2));
+            driver.run_with(&mut bot, Cursor::new("0\n1\n2\n"));
+
+            assert_eq!(bot.answered, [3, answer, 23], "{:?}", panics);
+            assert_eq!(*WRITTEN.lock().unwrap(), bot.answered, "{:?}", panics);
+            assert_eq!(bot.recovered, recovered, "{:?}", panics);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+const BOARD_SIZE: usize = 9;
+
+// Local referees may start a game from another position than the empty board,
+// given in `State` notation in this environment variable. Codingame never
+// sets it.
+pub const START_ENV: &str = "UTTT_START";
+
+#[derive(Debug)]
+pub enum InputError {
+    // The referee closed stdin, the game is over.
+    Eof,
+    Io(io::Error),
+    Malformed { line: String, reason: String },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Eof => write!(f, "end of input"),
+            InputError::Io(error) => write!(f, "io error: {}", error),
+            InputError::Malformed { line, reason } => write!(f, "malformed line {:?}: {}", line, reason),
+        }
+    }
+}
+
+impl Error for InputError {}
+
+impl From<io::Error> for InputError {
+    fn from(error: io::Error) -> Self {
+        InputError::Io(error)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TurnInput {
+    // `None` on the first turn when we start.
+    pub opponent: Option<(usize, usize)>,
+    pub valid_actions: Vec<(usize, usize)>,
+}
+
+pub struct InputReader<B: BufRead> {
+    input: B,
+}
+
+impl InputReader<io::StdinLock<'static>> {
+    pub fn stdin() -> Self {
+        InputReader::new(io::stdin().lock())
+    }
+}
+
+impl<B: BufRead> InputReader<B> {
+    pub fn new(input: B) -> Self {
+        InputReader { input }
+    }
+
+    pub fn read_turn(&mut self) -> Result<TurnInput, InputError> {
+        let line = self.read_line()?;
+        let opponent = match parse_pair(&line)? {
+            (-1, -1) => None,
+            (row, col) => Some(to_coords(&line, row, col)?),
+        };
+
+        let line = self.read_line()?;
+        let count = line.trim().parse::<usize>().map_err(|error| malformed(&line, error))?;
+
+        let mut valid_actions = Vec::with_capacity(count);
+        for _ in 0..count {
+            let line = self.read_line()?;
+            let (row, col) = parse_pair(&line)?;
+            valid_actions.push(to_coords(&line, row, col)?);
+        }
+
+        Ok(TurnInput {
+            opponent,
+            valid_actions,
+        })
+    }
+
+    // Blocks until there is input to read.
+    pub fn wait(&mut self) -> Result<(), InputError> {
+        if self.input.fill_buf()?.is_empty() {
+            return Err(InputError::Eof);
+        }
+        Ok(())
+    }
+
+    pub fn read_line(&mut self) -> Result<String, InputError> {
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Err(InputError::Eof);
+        }
+        Ok(line)
+    }
+}
+
+fn parse_pair(line: &str) -> Result<(i32, i32), InputError> {
+    let values = line
+        .split_ascii_whitespace()
+        .map(|value| value.parse::<i32>().map_err(|error| malformed(line, error)))
+        .collect::<Result<Vec<_>, _>>()?;
+    match values[..] {
+        [row, col] => Ok((row, col)),
+        _ => Err(malformed(line, "expected two numbers")),
+    }
+}
+
+fn to_coords(line: &str, row: i32, col: i32) -> Result<(usize, usize), InputError> {
+    if (0..BOARD_SIZE as i32).contains(&row) && (0..BOARD_SIZE as i32).contains(&col) {
+        Ok((row as usize, col as usize))
+    } else {
+        Err(malformed(line, "coordinates out of range"))
+    }
+}
+
+fn malformed(line: &str, reason: impl fmt::Display) -> InputError {
+    InputError::Malformed {
+        line: line.trim_end().to_string(),
+        reason: reason.to_string(),
+    }
+}
@@ -0,0 +1,8 @@
+pub mod bot;
+pub mod input;
+pub mod reader;
+pub mod safety;
+pub mod time_manager;
+pub mod transcript;
+
+pub use super::*;
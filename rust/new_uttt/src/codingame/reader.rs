@@ -0,0 +1,53 @@
+use std::io::BufRead;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::Instant;
+
+use super::input::{InputError, InputReader};
+
+// Reads turn input on its own thread so the main thread can keep searching
+// while the opponent thinks. Every input is stamped with the time its first
+// byte arrived. The channel closes at the end of the input or on the first
+// input error, which is reported on stderr.
+pub struct BackgroundReader<T: Send + 'static> {
+    receiver: Receiver<(Instant, T)>,
+}
+
+impl<T: Send + 'static> BackgroundReader<T> {
+    pub fn spawn<B, F>(mut input: InputReader<B>, mut read: F) -> Self
+    where
+        B: BufRead + Send + 'static,
+        F: FnMut(&mut InputReader<B>) -> Result<T, InputError> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            loop {
+                let received = input.wait().and_then(|_| {
+                    let arrived = Instant::now();
+                    read(&mut input).map(|value| (arrived, value))
+                });
+                match received {
+                    Ok(received) => {
+                        if sender.send(received).is_err() {
+                            break;
+                        }
+                    }
+                    Err(InputError::Eof) => break,
+                    Err(error) => {
+                        eprintln!("{}", error);
+                        break;
+                    }
+                }
+            }
+        });
+        BackgroundReader { receiver }
+    }
+
+    pub fn recv(&self) -> Option<(Instant, T)> {
+        self.receiver.recv().ok()
+    }
+
+    pub fn try_recv(&self) -> Result<(Instant, T), TryRecvError> {
+        self.receiver.try_recv()
+    }
+}
@@ -0,0 +1,168 @@
+use std::time::{Duration, Instant};
+
+const OVERSHOOT_DECAY: f64 = 0.9;
+const MIN_BUDGET: Duration = Duration::from_millis(1);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Phase {
+    Opening,
+    Middlegame,
+    Endgame,
+}
+
+// Decides how long to search each turn. The clock of a turn starts when its
+// input arrives; the budget is the referee limit minus a safety margin and the
+// worst overshoot seen recently, scaled by the weight of the game phase
+// relative to the heaviest phase. The referee limit holds for every turn, so
+// the heaviest phase searches up to it and the lighter ones answer early,
+// leaving the rest of their turn to pondering in the reused tree.
+pub struct TimeManager {
+    pub first_turn_limit: Duration,
+    pub turn_limit: Duration,
+    pub safety_margin: Duration,
+    pub phase_weights: [f64; 3],
+
+    overshoot: Duration,
+    turn: usize,
+}
+
+impl TimeManager {
+    pub fn new(first_turn_limit: Duration, turn_limit: Duration, safety_margin: Duration) -> Self {
+        TimeManager {
+            first_turn_limit,
+            turn_limit,
+            safety_margin,
+            phase_weights: [1., 1., 1.],
+            overshoot: Duration::ZERO,
+            turn: 0,
+        }
+    }
+
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    pub fn overshoot(&self) -> Duration {
+        self.overshoot
+    }
+
+    pub fn limit(&self) -> Duration {
+        if self.turn == 0 {
+            self.first_turn_limit
+        } else {
+            self.turn_limit
+        }
+    }
+
+    pub fn budget(&self, phase: Phase) -> Duration {
+        let available = self
+            .limit()
+            .saturating_sub(self.safety_margin)
+            .saturating_sub(self.overshoot);
+        let heaviest = self.phase_weights.iter().copied().fold(0., f64::max);
+        let share = if heaviest > 0. {
+            self.phase_weights[phase as usize].max(0.) / heaviest
+        } else {
+            1.
+        };
+        available.mul_f64(share).max(MIN_BUDGET)
+    }
+
+    // Call right after the output of a turn is written, with the start of the
+    // turn and the budget the search was given.
+    pub fn finish_turn(&mut self, begin: Instant, budget: Duration) {
+        let overshoot = begin.elapsed().saturating_sub(budget);
+        self.overshoot = self.overshoot.mul_f64(OVERSHOOT_DECAY).max(overshoot);
+        if overshoot > Duration::ZERO {
+            eprintln!("turn {} overshoot {:?}", self.turn, overshoot);
+        }
+        self.turn += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHASES: [Phase; 3] = [Phase::Opening, Phase::Middlegame, Phase::Endgame];
+
+    fn time_manager() -> TimeManager {
+        let mut time_manager =
+            TimeManager::new(Duration::from_millis(1000), Duration::from_millis(100), Duration::from_millis(5));
+        time_manager.phase_weights = [0.8, 1., 1.];
+        time_manager
+    }
+
+    fn assert_millis(duration: Duration, millis: f64) {
+        assert!((duration.as_secs_f64() * 1000. - millis).abs() < 1e-6, "{:?} != {}ms", duration, millis);
+    }
+
+    // A turn that took `elapsed` from its start until now.
+    fn finish_after(time_manager: &mut TimeManager, elapsed: Duration, budget: Duration) {
+        time_manager.finish_turn(Instant::now() - elapsed, budget);
+    }
+
+    #[test]
+    fn first_turn_gets_its_own_limit() {
+        let mut time_manager = time_manager();
+        assert_eq!(time_manager.limit(), Duration::from_millis(1000));
+        assert_millis(time_manager.budget(Phase::Middlegame), 995.);
+
+        let budget = time_manager.budget(Phase::Middlegame);
+        finish_after(&mut time_manager, budget / 2, budget);
+        assert_eq!(time_manager.turn(), 1);
+        assert_eq!(time_manager.limit(), Duration::from_millis(100));
+        assert_millis(time_manager.budget(Phase::Middlegame), 95.);
+    }
+
+    #[test]
+    fn phases_share_by_weight() {
+        let mut time_manager = time_manager();
+        finish_after(&mut time_manager, Duration::ZERO, Duration::from_millis(995));
+        assert_millis(time_manager.budget(Phase::Opening), 76.);
+        assert_millis(time_manager.budget(Phase::Middlegame), 95.);
+        assert_millis(time_manager.budget(Phase::Endgame), 95.);
+
+        // only the heaviest phase uses the whole turn
+        time_manager.phase_weights = [0.5, 2., 1.];
+        assert_millis(time_manager.budget(Phase::Opening), 23.75);
+        assert_millis(time_manager.budget(Phase::Middlegame), 95.);
+        assert_millis(time_manager.budget(Phase::Endgame), 47.5);
+    }
+
+    #[test]
+    fn overrun_shortens_the_next_turns() {
+        let mut time_manager = time_manager();
+        finish_after(&mut time_manager, Duration::ZERO, Duration::from_millis(995));
+
+        let budget = time_manager.budget(Phase::Middlegame);
+        finish_after(&mut time_manager, budget + Duration::from_millis(10), budget);
+        let overshoot = time_manager.overshoot();
+        assert!(overshoot >= Duration::from_millis(10));
+        assert!(time_manager.budget(Phase::Middlegame) <= Duration::from_millis(85));
+
+        // a turn on time lets the overshoot decay
+        let budget = time_manager.budget(Phase::Middlegame);
+        finish_after(&mut time_manager, Duration::ZERO, budget);
+        assert_eq!(time_manager.overshoot(), overshoot.mul_f64(OVERSHOOT_DECAY));
+        assert!(time_manager.budget(Phase::Middlegame) > budget);
+    }
+
+    #[test]
+    fn budget_stays_within_the_limit() {
+        let mut time_manager = time_manager();
+        let weights = [[0.8, 1., 1.], [0., 0., 0.], [3., 0.1, -1.], [1., 1., 1.]];
+        for (turn, &weights) in weights.iter().cycle().take(12).enumerate() {
+            time_manager.phase_weights = weights;
+            for phase in PHASES {
+                let budget = time_manager.budget(phase);
+                assert!(budget <= time_manager.limit() - time_manager.safety_margin, "{:?} {:?}", weights, phase);
+                assert!(budget >= MIN_BUDGET);
+            }
+            // every third turn overruns by a lot
+            let budget = time_manager.budget(Phase::Middlegame);
+            let elapsed = if turn % 3 == 2 { Duration::from_millis(150) } else { budget / 2 };
+            finish_after(&mut time_manager, elapsed, budget);
+        }
+    }
+}
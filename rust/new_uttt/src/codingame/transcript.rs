@@ -0,0 +1,267 @@
+use std::error::Error;
+use std::fmt;
+
+use super::game::game_action::Action;
+use super::game::game_state::State;
+use super::game::record::GameRecord;
+use super::mcts::traits::GameState;
+
+use super::input::{InputError, InputReader, TurnInput};
+
+#[derive(Debug)]
+pub enum TranscriptError {
+    Input(InputError),
+    Empty,
+    // no sequence of our moves explains the referee's input from this turn on
+    Inconsistent { turn: usize },
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptError::Input(error) => write!(f, "{}", error),
+            TranscriptError::Empty => write!(f, "the transcript has no turns"),
+            TranscriptError::Inconsistent { turn } => write!(f, "no moves of ours explain turn {}", turn + 1),
+        }
+    }
+}
+
+impl Error for TranscriptError {}
+
+impl From<InputError> for TranscriptError {
+    fn from(error: InputError) -> Self {
+        TranscriptError::Input(error)
+    }
+}
+
+pub struct Turn {
+    pub input: TurnInput,
+    // our answer, when the transcript has it
+    pub answer: Option<Action>,
+}
+
+// Reads what the referee sent us during a game, turn after turn as on stdin.
+// Our answers may follow a turn on a line starting with >, e.g. "> 4 4".
+// Blank lines are skipped so a transcript copied from the replay view can be
+// pasted as is.
+pub fn read_turns(text: &str) -> Result<Vec<Turn>, TranscriptError> {
+    let mut turns = Vec::new();
+    let mut pending = String::new();
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        match line.trim().strip_prefix('>') {
+            Some(answer) => {
+                read_inputs(&pending, &mut turns)?;
+                pending.clear();
+
+                let turn = turns.last_mut().ok_or_else(|| malformed(line, "an answer before any turn"))?;
+                let answer = answer.parse::<Action>().map_err(|error| malformed(line, error))?;
+                turn.answer = Some(answer);
+            }
+            None => {
+                pending.push_str(line);
+                pending.push('\n');
+            }
+        }
+    }
+    read_inputs(&pending, &mut turns)?;
+    Ok(turns)
+}
+
+fn read_inputs(text: &str, turns: &mut Vec<Turn>) -> Result<(), TranscriptError> {
+    let mut input = InputReader::new(text.as_bytes());
+    loop {
+        match input.read_turn() {
+            Ok(input) => turns.push(Turn { input, answer: None }),
+            Err(InputError::Eof) => return Ok(()),
+            Err(error) => return Err(error.into()),
+        }
+    }
+}
+
+fn malformed(line: &str, reason: impl fmt::Display) -> TranscriptError {
+    TranscriptError::Input(InputError::Malformed {
+        line: line.trim_end().to_string(),
+        reason: reason.to_string(),
+    })
+}
+
+// Rebuilds the game from the referee's input. Missing answers of ours are
+// inferred: a move is kept when the opponent's reply is legal after it and
+// our legal moves then match the referee's list, backtracking when a later
+// turn contradicts it. Several moves can fit, the first one is taken then,
+// so paste our answers when the exact game matters. Our move after the last
+// turn is only known from its answer.
+pub fn import(text: &str, me: &str, opponent: &str) -> Result<GameRecord, TranscriptError> {
+    let turns = read_turns(text)?;
+    let first_turn = turns.first().ok_or(TranscriptError::Empty)?;
+
+    let mut record = if first_turn.input.opponent.is_none() {
+        GameRecord::new(me, opponent)
+    } else {
+        GameRecord::new(opponent, me)
+    };
+    let mut moves = Vec::new();
+    let mut deepest = 0;
+    if !infer(&State::default(), &turns, 0, &mut moves, &mut deepest) {
+        return Err(TranscriptError::Inconsistent { turn: deepest });
+    }
+
+    let mut state = State::default();
+    for action in moves {
+        record.push(action, None);
+        state = state.perform_action_copy(&action);
+    }
+    record.finish(state.outcome());
+    Ok(record)
+}
+
+fn infer(state: &State, turns: &[Turn], turn: usize, moves: &mut Vec<Action>, deepest: &mut usize) -> bool {
+    *deepest = (*deepest).max(turn);
+    let input = &turns[turn].input;
+
+    let mut state = state.clone();
+    if let Some((row, col)) = input.opponent {
+        let action = match Action::from_row_col(row, col) {
+            Ok(action) if state.possible_actions().contains(&action) => action,
+            _ => return false,
+        };
+        state = state.perform_action_copy(&action);
+        moves.push(action);
+    }
+
+    let mut ours = state.possible_actions().iter().map(|action| action.index()).collect::<Vec<_>>();
+    let mut referee = input
+        .valid_actions
+        .iter()
+        .filter_map(|&(row, col)| Action::from_row_col(row, col).ok())
+        .map(|action| action.index())
+        .collect::<Vec<_>>();
+    ours.sort_unstable();
+    referee.sort_unstable();
+
+    let candidates = match turns[turn].answer {
+        Some(answer) => vec![answer],
+        None if turn + 1 == turns.len() => Vec::new(),
+        None => state.possible_actions(),
+    };
+    let found = ours == referee
+        && (candidates.is_empty() && turn + 1 == turns.len()
+            || candidates.into_iter().any(|action| {
+                if !state.possible_actions().contains(&action) {
+                    return false;
+                }
+                moves.push(action);
+                let found = turn + 1 == turns.len()
+                    || infer(&state.perform_action_copy(&action), turns, turn + 1, moves, deepest);
+                if !found {
+                    moves.pop();
+                }
+                found
+            }));
+    if !found && input.opponent.is_some() {
+        moves.pop();
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game::test_util::random_game;
+    use super::*;
+
+    // What the referee sends to the player moving first or second, with our
+    // answers when `answers` is set.
+    fn transcript(moves: &[Action], first: bool, answers: bool) -> String {
+        let mut text = String::new();
+        let mut state = State::default();
+        for (ply, action) in moves.iter().enumerate() {
+            if (ply % 2 == 0) == first {
+                match ply.checked_sub(1) {
+                    Some(previous) => text.push_str(&format!("{}\n", moves[previous])),
+                    None => text.push_str("-1 -1\n"),
+                }
+                let actions = state.possible_actions();
+                text.push_str(&format!("{}\n", actions.len()));
+                for action in actions {
+                    text.push_str(&format!("{}\n", action));
+                }
+                if answers {
+                    text.push_str(&format!("> {}\n", action));
+                }
+            }
+            state = state.perform_action_copy(action);
+        }
+        text
+    }
+
+    #[test]
+    fn imports_the_answered_game() {
+        for seed in 0..5 {
+            let moves = random_game(seed, &State::default());
+            for first in [true, false] {
+                let record = import(&transcript(&moves, first, true), "me", "them").unwrap();
+                let imported = record.moves.iter().map(|recorded| recorded.action).collect::<Vec<_>>();
+                // the referee never tells us its last move when it ends the game
+                let known = if (moves.len() % 2 == 1) == first { moves.len() } else { moves.len() - 1 };
+                assert_eq!(imported, moves[..known]);
+                assert_eq!(record.first, if first { "me" } else { "them" });
+            }
+        }
+    }
+
+    #[test]
+    fn infers_missing_answers() {
+        for seed in 0..5 {
+            let moves = random_game(seed, &State::default());
+            for first in [true, false] {
+                let text = transcript(&moves, first, false);
+                let record = import(&text, "me", "them").unwrap();
+                let imported = record.moves.iter().map(|recorded| recorded.action).collect::<Vec<_>>();
+                // the inferred game may differ, but it has to explain every turn
+                assert!(record.replay().is_ok());
+                let mut explained = imported.clone();
+                if (explained.len() % 2 == 0) == first {
+                    // the move after our last turn is unknown without the answer
+                    explained.push(*moves.last().unwrap());
+                }
+                assert_eq!(transcript(&explained, first, false), text);
+            }
+        }
+    }
+
+    #[test]
+    fn backtracks_over_wrong_guesses() {
+        // our reply to the center has to send the opponent to the bottom right
+        // board, which only the last square of the center board does
+        let moves = [
+            Action::from_row_col(4, 4).unwrap(),
+            Action::from_row_col(5, 5).unwrap(),
+            Action::from_row_col(7, 7).unwrap(),
+            Action::from_row_col(4, 3).unwrap(),
+        ];
+        let after_center = State::default().perform_action_copy(&moves[0]);
+        assert_ne!(after_center.possible_actions()[0], moves[1]);
+
+        let record = import(&transcript(&moves, false, false), "me", "them").unwrap();
+        let imported = record.moves.iter().map(|recorded| recorded.action).collect::<Vec<_>>();
+        assert_eq!(imported, moves[..3]);
+    }
+
+    #[test]
+    fn reports_the_inconsistent_turn() {
+        let moves = random_game(1, &State::default());
+        // a third turn where the opponent plays on our first move
+        let mut text = transcript(&moves[..4], true, false);
+        text.push_str(&format!("{}\n1\n0 0\n", moves[0]));
+        match import(&text, "me", "them") {
+            Err(TranscriptError::Inconsistent { turn }) => assert_eq!(turn, 2),
+            _ => panic!("the corrupted transcript was imported"),
+        }
+    }
+
+    #[test]
+    fn rejects_an_answer_before_any_turn() {
+        assert!(matches!(read_turns("> 4 4\n"), Err(TranscriptError::Input(_))));
+        assert!(matches!(import("", "me", "them"), Err(TranscriptError::Empty)));
+    }
+}
@@ -0,0 +1,55 @@
+use super::ab::traits::Evaluator;
+use super::mcts::traits::GameState;
+
+use super::game_action::Action;
+use super::game_state::{State, UTTTResult};
+use super::masks::{LOCAL_MOVES, WIN_LINES};
+use super::player::Player;
+
+// center, corners and edges in that order of value
+const BOARD_WEIGHTS: [i32; 9] = [120, 100, 120, 100, 140, 100, 120, 100, 120];
+const GLOBAL_LINE_WEIGHTS: [i32; 3] = [0, 20, 200];
+const LOCAL_THREAT_WEIGHT: i32 = 15;
+const LOCAL_CENTER_WEIGHT: i32 = 3;
+
+#[derive(Clone, Copy, Default)]
+pub struct UtttEvaluator;
+
+impl UtttEvaluator {
+    fn side_score(state: &State, player: Player) -> i32 {
+        let won = state.global_states.won(player);
+        let blocked = state.global_states.won(player.other()) | state.global_states.drawn();
+
+        let mut score = (0..9)
+            .filter(|&global| won & 1 << global != 0)
+            .map(|global| BOARD_WEIGHTS[global])
+            .sum::<i32>();
+
+        score += WIN_LINES
+            .iter()
+            .filter(|&&line| line & blocked == 0)
+            .map(|&line| GLOBAL_LINE_WEIGHTS[((line & won).count_ones() as usize).min(2)])
+            .sum::<i32>();
+
+        for &global in state.global_states.playable_boards.iter() {
+            let mine = state.local_boards.board(global, player);
+            let theirs = state.local_boards.board(global, player.other());
+            let threats = WIN_LINES
+                .iter()
+                .filter(|&&line| line & theirs == 0 && (line & mine).count_ones() == 2)
+                .count() as i32;
+            score += threats * LOCAL_THREAT_WEIGHT * BOARD_WEIGHTS[global] / 100;
+            if mine & LOCAL_MOVES[4] != 0 {
+                score += LOCAL_CENTER_WEIGHT;
+            }
+        }
+        score
+    }
+}
+
+impl Evaluator<Player, State, UTTTResult, Action> for UtttEvaluator {
+    fn evaluate(&self, state: &State) -> i32 {
+        let to_move = state.next_player();
+        Self::side_score(state, to_move) - Self::side_score(state, to_move.other())
+    }
+}
@@ -1,21 +1,46 @@
 use super::game::masks::local_to_global;
-use super::game::masks::BOARD_MASK;
+use super::game::masks::{BOARD_MASK, LOCAL_MOVES};
+use std::error::Error;
+use std::fmt;
 use std::hash::Hash;
+use std::str::FromStr;
 
 use super::mcts::traits::GameAction;
 
+const BOARD_SIZE: usize = 9;
+pub const ACTION_COUNT: usize = BOARD_SIZE * BOARD_SIZE;
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Action {
     bits: usize,
 }
 impl Hash for Action {
-    fn hash<H: std::hash::Hasher>(&self, _: &mut H) {
-        self.bits;
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.bits.hash(state);
     }
 }
 
 impl GameAction for Action{}
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ActionError {
+    OutOfRange { row: usize, col: usize },
+    Index(usize),
+    Parse(String),
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionError::OutOfRange { row, col } => write!(f, "square {} {} is off the board", row, col),
+            ActionError::Index(index) => write!(f, "action index {} is not below {}", index, ACTION_COUNT),
+            ActionError::Parse(text) => write!(f, "cannot parse {:?} as a move", text),
+        }
+    }
+}
+
+impl Error for ActionError {}
+
 impl Action {
     //#[inline]
     pub fn from_coords(global: usize, local: usize) -> Self {
@@ -32,10 +57,127 @@ impl Action {
         self.bits & BOARD_MASK
     }
 
+    // Rows and columns of the full 9x9 board, as the referee sends them.
+    //#[inline]
+    pub fn from_row_col(row: usize, col: usize) -> Result<Self, ActionError> {
+        if row >= BOARD_SIZE || col >= BOARD_SIZE {
+            return Err(ActionError::OutOfRange { row, col });
+        }
+        Ok(Action::from_coords(row / 3 * 3 + col / 3, LOCAL_MOVES[row % 3 * 3 + col % 3]))
+    }
+    //#[inline]
+    pub fn to_row_col(self) -> (usize, usize) {
+        let local = local_to_global(self.local());
+        (self.global() / 3 * 3 + local / 3, self.global() % 3 * 3 + local % 3)
+    }
+
+    // Dense index in 0..81, board by board: `global * 9 + square`.
+    //#[inline]
+    pub fn index(self) -> usize {
+        self.global() * BOARD_SIZE + local_to_global(self.local())
+    }
+    //#[inline]
+    pub fn from_index(index: usize) -> Result<Self, ActionError> {
+        if index >= ACTION_COUNT {
+            return Err(ActionError::Index(index));
+        }
+        Ok(Action::from_coords(index / BOARD_SIZE, LOCAL_MOVES[index % BOARD_SIZE]))
+    }
+
+    // Column letter a-i followed by row number 1-9, "a1" is the top left square.
+    pub fn algebraic(self) -> String {
+        let (row, col) = self.to_row_col();
+        format!("{}{}", (b'a' + col as u8) as char, row + 1)
+    }
+
     //#[inline]
     pub fn print(&self) {
-        let row = self.global() / 3 * 3 + local_to_global(self.local()) / 3;
-        let col = self.global() % 3 * 3 + local_to_global(self.local()) % 3;
-        println!("{} {}", row, col);
+        println!("{}", self);
+    }
+}
+
+// "row col", the Codingame format.
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (row, col) = self.to_row_col();
+        write!(f, "{} {}", row, col)
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Debug for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.algebraic())
+    }
+}
+
+// Accepts both "row col" and the algebraic notation.
+impl FromStr for Action {
+    type Err = ActionError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || ActionError::Parse(text.to_string());
+        let trimmed = text.trim();
+
+        let mut numbers = trimmed.split_ascii_whitespace();
+        if let (Some(row), Some(col), None) = (numbers.next(), numbers.next(), numbers.next()) {
+            let row = row.parse::<usize>().map_err(|_| error())?;
+            let col = col.parse::<usize>().map_err(|_| error())?;
+            return Action::from_row_col(row, col);
+        }
+
+        let mut chars = trimmed.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(letter @ 'a'..='i'), Some(digit @ '1'..='9'), None) => {
+                Action::from_row_col(digit as usize - '1' as usize, letter as usize - 'a' as usize)
+            }
+            _ => Err(error()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_col_round_trip() {
+        for row in 0..9 {
+            for col in 0..9 {
+                let action = Action::from_row_col(row, col).unwrap();
+                assert_eq!(action.to_row_col(), (row, col));
+            }
+        }
+        assert_eq!(Action::from_row_col(9, 0), Err(ActionError::OutOfRange { row: 9, col: 0 }));
+    }
+
+    #[test]
+    fn index_round_trip() {
+        for index in 0..ACTION_COUNT {
+            assert_eq!(Action::from_index(index).unwrap().index(), index);
+        }
+        assert_eq!(Action::from_index(ACTION_COUNT), Err(ActionError::Index(ACTION_COUNT)));
+        // board by board, the center of the center board sits in the middle
+        assert_eq!(Action::from_row_col(4, 4).unwrap().index(), 40);
+        assert_eq!(Action::from_row_col(0, 3).unwrap().index(), 9);
+    }
+
+    #[test]
+    fn parses_both_notations() {
+        for index in 0..ACTION_COUNT {
+            let action = Action::from_index(index).unwrap();
+            assert_eq!(action.to_string().parse::<Action>(), Ok(action));
+            assert_eq!(action.algebraic().parse::<Action>(), Ok(action));
+        }
+        assert_eq!(" 2 7\n".parse::<Action>(), Action::from_row_col(2, 7));
+        assert_eq!("a1".parse::<Action>(), Action::from_row_col(0, 0));
+        assert_eq!("i9".parse::<Action>(), Action::from_row_col(8, 8));
+    }
+
+    #[test]
+    fn rejects_bad_moves() {
+        for text in ["", "4", "4 4 4", "j1", "a0", "a10", "x y"] {
+            assert_eq!(text.parse::<Action>(), Err(ActionError::Parse(text.to_string())));
+        }
+        assert_eq!("9 0".parse::<Action>(), Err(ActionError::OutOfRange { row: 9, col: 0 }));
+    }
+}
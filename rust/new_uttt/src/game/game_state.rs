@@ -1,7 +1,14 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
 use super::cg_rand::Rng;
 
-use super::masks::local_to_global;
+use super::ab::traits::PositionKey;
+
+use super::masks::{local_to_global, LOCAL_MOVES, WIN_LINES};
 
+use super::mcts::traits::Determinize;
 use super::mcts::traits::GameState;
 
 use super::mcts::traits::GameResult;
@@ -10,6 +17,8 @@ use super::game_action::Action;
 use super::global_board::GlobalBoard;
 use super::local_board::LocalBoards;
 use super::player::Player;
+use super::symmetry::Symmetry;
+use super::zobrist::{FORCED_KEYS, SIDE_KEY, SQUARE_KEYS};
 
 #[derive(PartialEq, Clone, Copy, Debug)]
 pub enum UTTTResult {
@@ -28,6 +37,8 @@ pub struct State {
     pub local_boards: LocalBoards,
     pub global_states: GlobalBoard,
     pub result: UTTTResult,
+    // Zobrist hash of the occupied squares only
+    hash: u64,
 }
 
 impl Default for State {
@@ -60,6 +71,9 @@ impl GameState<Player, UTTTResult, Action> for State {
 
     //#[inline]
     fn possible_actions(&self) -> Vec<Action> {
+        if !self.playable() {
+            return Vec::new();
+        }
         match self.last_local_move {
             Some(last_local) => {
                 let global = local_to_global(last_local);
@@ -108,6 +122,7 @@ impl GameState<Player, UTTTResult, Action> for State {
         new_state.result = new_state.global_states.set(action.global(), board_state);
         new_state.last_action = Some(*action);
         new_state.last_local_move = Some(action.local());
+        new_state.hash ^= SQUARE_KEYS[new_state.player as usize][action.index()];
 
         new_state
     }
@@ -138,6 +153,19 @@ impl GameState<Player, UTTTResult, Action> for State {
     }
 }
 
+// UTTT has no hidden information, every determinization is the state itself.
+impl Determinize<Player, UTTTResult, Action> for State {
+    fn determinize(&self, _observer: &Player, _rng: &mut Box<Rng>) -> Self {
+        self.clone()
+    }
+}
+
+impl PositionKey for State {
+    fn key(&self) -> u64 {
+        self.zobrist()
+    }
+}
+
 impl State {
     //#[inline]
     pub fn new() -> State {
@@ -148,13 +176,202 @@ impl State {
             local_boards: Default::default(),
             global_states: Default::default(),
             result: UTTTResult::InPlay,
+            hash: 0,
+        }
+    }
+
+    // Zobrist hash of the position: the squares, the player to move and the
+    // forced board. Kept up to date by `perform_action_copy`, but not during
+    // playouts.
+    //#[inline]
+    pub fn zobrist(&self) -> u64 {
+        self.hash ^ self.context_hash(Symmetry::IDENTITY)
+    }
+
+    // Hash of the position transformed by `symmetry`, computed from scratch.
+    pub fn zobrist_under(&self, symmetry: Symmetry) -> u64 {
+        self.squares_hash(symmetry) ^ self.context_hash(symmetry)
+    }
+
+    // Smallest hash over the symmetric positions, together with the symmetry
+    // that maps this position onto the one it belongs to.
+    pub fn canonical(&self) -> (u64, Symmetry) {
+        Symmetry::ALL
+            .iter()
+            .map(|&symmetry| (self.zobrist_under(symmetry), symmetry))
+            .min_by_key(|&(hash, _)| hash)
+            .unwrap()
+    }
+
+    //#[inline]
+    pub fn canonical_hash(&self) -> u64 {
+        self.canonical().0
+    }
+
+    // The symmetries that leave this position unchanged, the identity
+    // included.
+    pub fn symmetries(&self) -> Vec<Symmetry> {
+        Symmetry::ALL
+            .iter()
+            .copied()
+            .filter(|&symmetry| {
+                let forced = self.forced_board();
+                forced.map(|global| symmetry.board(global)) == forced
+                    && (0..81).all(|index| {
+                        let square = Action::from_index(index).unwrap();
+                        let image = symmetry.action(square);
+                        [Player::X, Player::O].iter().all(|&player| {
+                            (self.local_boards.board(square.global(), player) & square.local() != 0)
+                                == (self.local_boards.board(image.global(), player) & image.local() != 0)
+                        })
+                    })
+            })
+            .collect()
+    }
+
+    // The legal actions grouped into classes that lead to the same position up
+    // to symmetry.
+    pub fn equivalent_actions(&self) -> Vec<Vec<Action>> {
+        let symmetries = self.symmetries();
+        let actions = self.possible_actions();
+        let mut seen = Vec::with_capacity(actions.len());
+        let mut classes = Vec::new();
+        for &action in &actions {
+            if seen.contains(&action) {
+                continue;
+            }
+            let mut class = Vec::new();
+            for symmetry in &symmetries {
+                let image = symmetry.action(action);
+                if !class.contains(&image) {
+                    class.push(image);
+                }
+            }
+            seen.extend(class.iter().copied());
+            classes.push(class);
+        }
+        classes
+    }
+
+    fn squares_hash(&self, symmetry: Symmetry) -> u64 {
+        let mut hash = 0;
+        for global in 0..9 {
+            for (player, keys) in [(Player::X, &SQUARE_KEYS[0]), (Player::O, &SQUARE_KEYS[1])] {
+                let board = self.local_boards.board(global, player);
+                for square in 0..9 {
+                    if board & 1 << square != 0 {
+                        hash ^= keys[symmetry.index(global * 9 + square)];
+                    }
+                }
+            }
         }
+        hash
+    }
+
+    fn context_hash(&self, symmetry: Symmetry) -> u64 {
+        let mut hash = 0;
+        if self.next_player() == Player::X {
+            hash ^= SIDE_KEY;
+        }
+        if let Some(global) = self.forced_board() {
+            hash ^= FORCED_KEYS[symmetry.board(global)];
+        }
+        hash
+    }
+
+    // The board the next move has to be played in, `None` when free to choose.
+    //#[inline]
+    pub fn forced_board(&self) -> Option<usize> {
+        self.last_local_move
+            .map(local_to_global)
+            .filter(|&global| self.global_states.in_play(global))
+    }
+
+    //#[inline]
+    pub fn square(&self, row: usize, col: usize) -> Option<Player> {
+        let square = Action::from_row_col(row, col).ok()?;
+        if self.local_boards.board(square.global(), Player::X) & square.local() != 0 {
+            Some(Player::X)
+        } else if self.local_boards.board(square.global(), Player::O) & square.local() != 0 {
+            Some(Player::O)
+        } else {
+            None
+        }
+    }
+
+    // 9x9 grid with the local boards separated, and next to it the global
+    // board: X or O for won boards, = for drawn ones and * for the forced one.
+    pub fn render(&self) -> String {
+        let mut rendered = String::new();
+        for row in 0..9 {
+            if row > 0 && row % 3 == 0 {
+                rendered.push_str("------+-------+------\n");
+            }
+            for col in 0..9 {
+                if col > 0 && col % 3 == 0 {
+                    rendered.push_str("| ");
+                }
+                rendered.push(square_char(self.square(row, col)));
+                if col < 8 {
+                    rendered.push(' ');
+                }
+            }
+            if row % 3 == 1 {
+                rendered.push_str("    ");
+                for global in row / 3 * 3..row / 3 * 3 + 3 {
+                    rendered.push(self.board_char(global));
+                }
+            }
+            rendered.push('\n');
+        }
+
+        match self.result {
+            UTTTResult::InPlay => match self.forced_board() {
+                Some(global) => {
+                    rendered.push_str(&format!("{:?} to move in board {}\n", self.next_player(), global))
+                }
+                None => rendered.push_str(&format!("{:?} to move anywhere\n", self.next_player())),
+            },
+            UTTTResult::Won(player) => rendered.push_str(&format!("{:?} won\n", player)),
+            UTTTResult::Drawn => rendered.push_str("drawn\n"),
+        }
+        rendered
+    }
+
+    fn board_char(&self, global: usize) -> char {
+        let bit = 1 << global;
+        if self.global_states.drawn() & bit != 0 {
+            '='
+        } else if self.global_states.won(Player::X) & bit != 0 {
+            'X'
+        } else if self.global_states.won(Player::O) & bit != 0 {
+            'O'
+        } else if self.forced_board() == Some(global) {
+            '*'
+        } else {
+            '.'
+        }
+    }
+
+    fn score_board(&mut self, global: usize, board_state: UTTTResult) {
+        let result = self.global_states.set(global, board_state);
+        if self.result == UTTTResult::InPlay {
+            self.result = result;
+        }
+    }
+
+    //#[inline]
+    pub fn empty_squares(&self) -> usize {
+        self.global_states
+            .playable_boards
+            .iter()
+            .map(|&global| self.local_boards.local_moves(global).len())
+            .sum()
     }
 
     //#[inline]
     pub fn random_move(&self, rng: &mut Box<Rng>) -> Option<Action> {
-        let global = local_to_global(self.last_local_move.unwrap());
-        if self.global_states.in_play(global) {
+        if let Some(global) = self.forced_board() {
             rng.choice(self.local_boards.local_moves(global).iter())
                 .map(|&local| Action::from_coords(global, local))
         } else {
@@ -167,3 +384,261 @@ impl State {
         }
     }
 }
+
+fn square_char(square: Option<Player>) -> char {
+    match square {
+        Some(Player::X) => 'X',
+        Some(Player::O) => 'O',
+        None => '.',
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseStateError(String);
+
+impl fmt::Display for ParseStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid position: {}", self.0)
+    }
+}
+
+impl Error for ParseStateError {}
+
+// Position notation: the nine rows of the board as X, O and . separated by
+// slashes, the player to move and the forced board or - when free to choose,
+// e.g. "...../...../.. O 4". The last move is not part of it.
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..9 {
+            if row > 0 {
+                write!(f, "/")?;
+            }
+            for col in 0..9 {
+                write!(f, "{}", square_char(self.square(row, col)))?;
+            }
+        }
+        write!(f, " {:?} ", self.next_player())?;
+        match self.forced_board() {
+            Some(global) => write!(f, "{}", global),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+impl FromStr for State {
+    type Err = ParseStateError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = |reason: &str| ParseStateError(reason.to_string());
+
+        let fields = text.split_ascii_whitespace().collect::<Vec<_>>();
+        let (grid, to_move, forced) = match fields[..] {
+            [grid, to_move, forced] => (grid, to_move, forced),
+            _ => return Err(error("expected grid, player to move and forced board")),
+        };
+        let rows = grid.split('/').collect::<Vec<_>>();
+        if rows.len() != 9 || rows.iter().any(|row| row.len() != 9) {
+            return Err(error("the grid needs nine rows of nine squares"));
+        }
+        let to_move = match to_move {
+            "X" => Player::X,
+            "O" => Player::O,
+            _ => return Err(error("the player to move is X or O")),
+        };
+
+        let mut state = State::new();
+        let mut counts = [0, 0];
+        for (row, line) in rows.iter().enumerate() {
+            for (col, cell) in line.chars().enumerate() {
+                let player = match cell {
+                    'X' => Player::X,
+                    'O' => Player::O,
+                    '.' => continue,
+                    _ => return Err(error("squares are X, O or .")),
+                };
+                counts[player as usize] += 1;
+
+                let square = Action::from_row_col(row, col).unwrap();
+                state.local_boards.set(square.global(), square.local(), player);
+            }
+        }
+
+        // the grid does not tell in which order the squares were played, so
+        // the local boards are only scored once they are complete. A drawn
+        // board counts for both players in a line, so drawn boards go last,
+        // as they must have been drawn after the wins if the game goes on.
+        let mut drawn = Vec::new();
+        for global in 0..9 {
+            let won = |player| {
+                let board = state.local_boards.board(global, player);
+                WIN_LINES.iter().any(|&line| line & !board == 0)
+            };
+            let board_state = match (won(Player::X), won(Player::O)) {
+                (true, true) => return Err(error("both players won the same board")),
+                (true, false) => UTTTResult::Won(Player::X),
+                (false, true) => UTTTResult::Won(Player::O),
+                _ if state.local_boards.local_moves(global).is_empty() => {
+                    drawn.push(global);
+                    continue;
+                }
+                _ => continue,
+            };
+            state.score_board(global, board_state);
+        }
+        for global in drawn {
+            state.score_board(global, UTTTResult::Drawn);
+        }
+
+        // O moves first, so O is to move whenever both played as often
+        let expected = if counts[Player::O as usize] == counts[Player::X as usize] {
+            Player::O
+        } else if counts[Player::O as usize] == counts[Player::X as usize] + 1 {
+            Player::X
+        } else {
+            return Err(error("X and O did not alternate"));
+        };
+        if to_move != expected {
+            return Err(error("wrong player to move for this grid"));
+        }
+        state.player = to_move.other();
+        state.hash = state.squares_hash(Symmetry::IDENTITY);
+
+        if forced != "-" {
+            let global = forced
+                .parse::<usize>()
+                .ok()
+                .filter(|&global| global < 9)
+                .ok_or_else(|| error("the forced board is 0-8 or -"))?;
+            if !state.global_states.in_play(global) {
+                return Err(error("the forced board is already finished"));
+            }
+            state.last_local_move = Some(LOCAL_MOVES[global]);
+        }
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util::random_game;
+    use super::*;
+
+    // The positions in play of a few seeded random games. A finished game can
+    // read as still in play: a line completed by a drawn board only counts if
+    // the draw came first, and the grid does not tell.
+    fn random_positions() -> Vec<State> {
+        let mut positions = Vec::new();
+        for seed in 0..20 {
+            let mut state = State::default();
+            for action in random_game(seed, &state) {
+                positions.push(state.clone());
+                state = state.perform_action_copy(&action);
+            }
+        }
+        positions
+    }
+
+    fn sorted_actions(state: &State) -> Vec<usize> {
+        let mut actions = state.possible_actions().into_iter().map(Action::index).collect::<Vec<_>>();
+        actions.sort();
+        actions
+    }
+
+    #[test]
+    fn notation_round_trip() {
+        for state in random_positions() {
+            let text = state.to_string();
+            let parsed = text.parse::<State>().unwrap();
+            assert_eq!(parsed.to_string(), text);
+            assert_eq!(parsed.next_player(), state.next_player());
+            assert_eq!(parsed.forced_board(), state.forced_board());
+            assert!(parsed.playable(), "{}", text);
+            assert_eq!(sorted_actions(&parsed), sorted_actions(&state), "{}", text);
+        }
+    }
+
+    #[test]
+    fn empty_board_notation() {
+        let text = "........./........./........./........./........./........./........./........./......... O -";
+        assert_eq!(State::default().to_string(), text);
+        assert_eq!(text.parse::<State>().unwrap().possible_actions().len(), 81);
+    }
+
+    // The same game with every move transformed by `symmetry`.
+    fn transformed(state: &State, symmetry: Symmetry, moves: &[Action]) -> State {
+        moves
+            .iter()
+            .fold(state.clone(), |state, &action| state.perform_action_copy(&symmetry.action(action)))
+    }
+
+    #[test]
+    fn incremental_hash_matches_a_fresh_one() {
+        for state in random_positions() {
+            assert_eq!(state.zobrist(), state.zobrist_under(Symmetry::IDENTITY), "{}", state);
+            let parsed = state.to_string().parse::<State>().unwrap();
+            assert_eq!(parsed.zobrist(), state.zobrist(), "{}", state);
+        }
+    }
+
+    #[test]
+    fn symmetric_positions_share_the_canonical_hash() {
+        let mut rng: Box<Rng> = Box::new(Rng::with_seed(11));
+        for _ in 0..10 {
+            let mut state = State::default();
+            let mut moves = Vec::new();
+            for _ in 0..12 {
+                if !state.playable() {
+                    break;
+                }
+                let action = state.random_move(&mut rng).unwrap();
+                moves.push(action);
+                state = state.perform_action_copy(&action);
+            }
+            for symmetry in Symmetry::ALL {
+                let image = transformed(&State::default(), symmetry, &moves);
+                assert_eq!(image.zobrist(), state.zobrist_under(symmetry), "{:?}", symmetry);
+                assert_eq!(image.canonical_hash(), state.canonical_hash(), "{:?}", symmetry);
+            }
+        }
+    }
+
+    #[test]
+    fn different_positions_hash_differently() {
+        let center = State::default().perform_action_copy(&Action::from_row_col(4, 4).unwrap());
+        let corner = State::default().perform_action_copy(&Action::from_row_col(0, 0).unwrap());
+        assert_ne!(State::default().zobrist(), center.zobrist());
+        assert_ne!(center.canonical_hash(), corner.canonical_hash());
+    }
+
+    #[test]
+    fn empty_board_symmetries() {
+        let state = State::default();
+        assert_eq!(state.symmetries().len(), 8);
+        // the orbits of the 81 squares under the 8 symmetries
+        let classes = state.equivalent_actions();
+        assert_eq!(classes.len(), 15);
+        assert_eq!(classes.iter().map(Vec::len).sum::<usize>(), 81);
+
+        let center = state.perform_action_copy(&Action::from_row_col(4, 4).unwrap());
+        assert_eq!(center.symmetries().len(), 8);
+        let corner = state.perform_action_copy(&Action::from_row_col(0, 0).unwrap());
+        assert_eq!(corner.symmetries(), vec![Symmetry::IDENTITY, Symmetry::ALL[6]]);
+    }
+
+    #[test]
+    fn rejects_bad_notation() {
+        let empty = "........./........./........./........./........./........./........./........./.........";
+        for text in [
+            String::new(),
+            format!("{} O", empty),
+            format!("{} Z -", empty),
+            format!("{} X -", empty),
+            format!("{} O 9", empty),
+            format!("{}/......... O -", empty),
+            format!("{} O -", empty.replacen('.', "?", 1)),
+            format!("{} O -", empty.replacen('.', "X", 2)),
+        ] {
+            assert!(text.parse::<State>().is_err(), "{}", text);
+        }
+    }
+}
@@ -39,6 +39,19 @@ impl GlobalBoard {
         self.playable_boards.contains(&board)
     }
 
+    //#[inline]
+    pub fn won(&self, player: Player) -> usize {
+        match player {
+            Player::X => self.x & !self.o,
+            Player::O => self.o & !self.x,
+        }
+    }
+
+    //#[inline]
+    pub fn drawn(&self) -> usize {
+        self.x & self.o
+    }
+
     //#[inline]
     pub fn set(&mut self, board: usize, state: UTTTResult) -> UTTTResult {
         let bit = 1_usize << board;
@@ -74,7 +87,7 @@ impl GlobalBoard {
 
                 if win_masks_for_move(bit)
                     .iter()
-                    .any(|&win_mask| self.x & win_mask == win_mask)
+                    .any(|&win_mask| win_mask & !self.x == 0)
                 {
                     return UTTTResult::Won(Player::X);
                 }
@@ -100,7 +113,7 @@ impl GlobalBoard {
 
                 if win_masks_for_move(bit)
                     .iter()
-                    .any(|&win_mask| self.o & win_mask == win_mask)
+                    .any(|&win_mask| win_mask & !self.o == 0)
                 {
                     return UTTTResult::Won(Player::O);
                 }
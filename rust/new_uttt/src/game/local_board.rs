@@ -38,6 +38,14 @@ impl LocalBoards {
         &self.legal_moves[global]
     }
 
+    //#[inline]
+    pub fn board(&self, global: usize, player: Player) -> usize {
+        match player {
+            Player::X => self.boards_x[global],
+            Player::O => self.boards_o[global],
+        }
+    }
+
     //#[inline]
     pub fn set(&mut self, global: usize, local: usize, player: Player) -> UTTTResult {
         match self.legal_moves[global].iter().position(|&loc| loc == local) {
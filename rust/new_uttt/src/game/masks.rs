@@ -14,6 +14,17 @@ pub const fn win_masks_for_move(local: usize) -> &'static [usize] {
     }
 }
 
+pub const WIN_LINES: &[usize] = &[
+    0b000_000_111,
+    0b000_111_000,
+    0b111_000_000,
+    0b001_001_001,
+    0b010_010_010,
+    0b100_100_100,
+    0b100_010_001,
+    0b001_010_100,
+];
+
 pub const LOCAL_MOVES: &[usize] = &[
     0b000_000_001,
     0b000_000_010,
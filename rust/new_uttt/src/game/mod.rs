@@ -4,5 +4,11 @@ pub mod game_state;
 pub mod player;
 pub mod local_board;
 pub mod global_board;
+pub mod evaluation;
+pub mod record;
+pub mod symmetry;
+pub mod zobrist;
+#[cfg(test)]
+pub mod test_util;
 
 pub use super::*;
\ No newline at end of file
@@ -1,7 +1,7 @@
 use super::game_state::UTTTResult;
 use super::mcts::traits::GamePlayer;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Player {
     X,
     O,
@@ -0,0 +1,298 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::mcts::traits::GameState;
+
+use super::game_action::Action;
+use super::game_state::{State, UTTTResult};
+use super::player::Player;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RecordResult {
+    FirstWon,
+    SecondWon,
+    Drawn,
+    Unfinished,
+}
+
+impl RecordResult {
+    pub fn from_outcome(outcome: UTTTResult, first_mover: Player) -> Self {
+        match outcome {
+            UTTTResult::Won(winner) if winner == first_mover => RecordResult::FirstWon,
+            UTTTResult::Won(_) => RecordResult::SecondWon,
+            UTTTResult::Drawn => RecordResult::Drawn,
+            UTTTResult::InPlay => RecordResult::Unfinished,
+        }
+    }
+
+    fn notation(self) -> &'static str {
+        match self {
+            RecordResult::FirstWon => "1-0",
+            RecordResult::SecondWon => "0-1",
+            RecordResult::Drawn => "1/2-1/2",
+            RecordResult::Unfinished => "*",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RecordedMove {
+    pub action: Action,
+    // time the player took, when known
+    pub time: Option<Duration>,
+}
+
+// A played game: who played it, how the bots were seeded and every move.
+// Games may start from a given position instead of the empty board.
+#[derive(Clone)]
+pub struct GameRecord {
+    pub first: String,
+    pub second: String,
+    pub seeds: Vec<u64>,
+    pub start: Option<State>,
+    pub moves: Vec<RecordedMove>,
+    pub result: RecordResult,
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    Malformed { line: usize, reason: String },
+    IllegalMove { ply: usize, action: Action },
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::Malformed { line, reason } => write!(f, "line {}: {}", line, reason),
+            RecordError::IllegalMove { ply, action } => write!(f, "move {} ({}) is illegal", ply + 1, action),
+        }
+    }
+}
+
+impl Error for RecordError {}
+
+impl GameRecord {
+    pub fn new(first: &str, second: &str) -> Self {
+        GameRecord {
+            first: first.to_string(),
+            second: second.to_string(),
+            seeds: Vec::new(),
+            start: None,
+            moves: Vec::new(),
+            result: RecordResult::Unfinished,
+        }
+    }
+
+    pub fn push(&mut self, action: Action, time: Option<Duration>) {
+        self.moves.push(RecordedMove { action, time });
+    }
+
+    pub fn finish(&mut self, outcome: UTTTResult) {
+        self.result = RecordResult::from_outcome(outcome, self.start_state().next_player());
+    }
+
+    pub fn start_state(&self) -> State {
+        self.start.clone().unwrap_or_default()
+    }
+
+    // Every state of the game, from the start position to the one after the
+    // last move, checking that each move was legal.
+    pub fn replay(&self) -> Result<Vec<State>, RecordError> {
+        let mut states = vec![self.start_state()];
+        for (ply, recorded) in self.moves.iter().enumerate() {
+            let state = states.last().unwrap();
+            if !state.possible_actions().contains(&recorded.action) {
+                return Err(RecordError::IllegalMove {
+                    ply,
+                    action: recorded.action,
+                });
+            }
+            states.push(state.perform_action_copy(&recorded.action));
+        }
+        Ok(states)
+    }
+}
+
+// Header lines of `key value`, then `moves` and one `row col time` line per
+// move, the time in milliseconds or - when unknown:
+//
+// first mcts
+// second uct
+// seeds 1 2
+// result 1-0
+// moves
+// 4 4 98
+// 3 3 -
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "first {}", self.first)?;
+        writeln!(f, "second {}", self.second)?;
+        if !self.seeds.is_empty() {
+            let seeds = self.seeds.iter().map(|seed| seed.to_string()).collect::<Vec<_>>();
+            writeln!(f, "seeds {}", seeds.join(" "))?;
+        }
+        if let Some(start) = &self.start {
+            writeln!(f, "start {}", start)?;
+        }
+        writeln!(f, "result {}", self.result.notation())?;
+        writeln!(f, "moves")?;
+        for recorded in &self.moves {
+            match recorded.time {
+                Some(time) => writeln!(f, "{} {}", recorded.action, time.as_millis())?,
+                None => writeln!(f, "{} -", recorded.action)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = RecordError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut record = GameRecord::new("", "");
+        let mut in_moves = false;
+
+        for (index, line) in text.lines().enumerate() {
+            let malformed = |reason: &str| RecordError::Malformed {
+                line: index + 1,
+                reason: reason.to_string(),
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if in_moves {
+                let (square, time) = line.rsplit_once(' ').ok_or_else(|| malformed("expected row col time"))?;
+                let action = square.parse::<Action>().map_err(|error| malformed(&error.to_string()))?;
+                let time = match time {
+                    "-" => None,
+                    millis => Some(Duration::from_millis(
+                        millis.parse().map_err(|_| malformed("the time is in milliseconds or -"))?,
+                    )),
+                };
+                record.push(action, time);
+                continue;
+            }
+
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "first" => record.first = value.to_string(),
+                "second" => record.second = value.to_string(),
+                "seeds" => {
+                    record.seeds = value
+                        .split_ascii_whitespace()
+                        .map(|seed| seed.parse())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| malformed("seeds are unsigned numbers"))?
+                }
+                "start" => {
+                    let start = value.parse::<State>().map_err(|error| malformed(&error.to_string()))?;
+                    record.start = Some(start);
+                }
+                "result" => {
+                    record.result = match value {
+                        "1-0" => RecordResult::FirstWon,
+                        "0-1" => RecordResult::SecondWon,
+                        "1/2-1/2" => RecordResult::Drawn,
+                        "*" => RecordResult::Unfinished,
+                        _ => return Err(malformed("the result is 1-0, 0-1, 1/2-1/2 or *")),
+                    }
+                }
+                "moves" => in_moves = true,
+                _ => return Err(malformed("unknown header")),
+            }
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_util;
+    use super::*;
+
+    fn random_game(start: Option<State>, seed: u64) -> GameRecord {
+        let mut record = GameRecord::new("mcts", "random bot");
+        record.seeds = vec![seed, seed + 1];
+        record.start = start;
+        let mut state = record.start_state();
+        for action in test_util::random_game(seed, &state) {
+            // every other move without a time
+            let ply = record.moves.len();
+            let time = ply.is_multiple_of(2).then(|| Duration::from_millis(ply as u64));
+            record.push(action, time);
+            state = state.perform_action_copy(&action);
+        }
+        record.finish(state.outcome());
+        record
+    }
+
+    fn assert_round_trip(record: &GameRecord) {
+        let text = record.to_string();
+        let parsed = text.parse::<GameRecord>().unwrap();
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(parsed.first, record.first);
+        assert_eq!(parsed.second, record.second);
+        assert_eq!(parsed.seeds, record.seeds);
+        assert_eq!(parsed.moves, record.moves);
+        assert_eq!(parsed.result, record.result);
+        assert_eq!(parsed.replay().unwrap().len(), record.moves.len() + 1);
+    }
+
+    #[test]
+    fn round_trip() {
+        for seed in 0..10 {
+            assert_round_trip(&random_game(None, seed));
+        }
+    }
+
+    #[test]
+    fn round_trip_from_start() {
+        let start = State::default().perform_action_copy(&Action::from_row_col(4, 4).unwrap());
+        let record = random_game(Some(start.clone()), 3);
+        assert_round_trip(&record);
+        let parsed = record.to_string().parse::<GameRecord>().unwrap();
+        assert_eq!(parsed.start.map(|start| start.to_string()), Some(start.to_string()));
+    }
+
+    #[test]
+    fn parses_the_documented_example() {
+        let text = "first mcts\nsecond uct\nseeds 1 2\nresult 1-0\nmoves\n4 4 98\n3 3 -\n";
+        let record = text.parse::<GameRecord>().unwrap();
+        assert_eq!(record.seeds, vec![1, 2]);
+        assert_eq!(record.result, RecordResult::FirstWon);
+        assert_eq!(record.moves[0].time, Some(Duration::from_millis(98)));
+        assert_eq!(record.moves[1].time, None);
+        assert_eq!(record.to_string(), text);
+    }
+
+    #[test]
+    fn rejects_bad_records() {
+        for (text, line) in [
+            ("first a\nwinner a\n", 2),
+            ("result 2-0\n", 1),
+            ("seeds 1 x\n", 1),
+            ("moves\n4 4 soon\n", 2),
+            ("moves\n9 9 1\n", 2),
+        ] {
+            match text.parse::<GameRecord>() {
+                Err(RecordError::Malformed { line: at, .. }) => assert_eq!(at, line, "{:?}", text),
+                _ => panic!("{:?} parsed", text),
+            }
+        }
+    }
+
+    #[test]
+    fn replay_rejects_illegal_moves() {
+        // the second move has to go to the center board
+        let record = "moves\n4 4 -\n0 0 -\n".parse::<GameRecord>().unwrap();
+        match record.replay() {
+            Err(RecordError::IllegalMove { ply, .. }) => assert_eq!(ply, 1),
+            _ => panic!("the illegal move was replayed"),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use super::game_action::{Action, ACTION_COUNT};
+
+// One of the 8 symmetries of the square. Applied to the full 9x9 board it maps
+// global boards onto global boards and squares within them the same way, so
+// it is a symmetry of the game as long as the forced board follows along.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Symmetry(u8);
+
+impl Symmetry {
+    pub const IDENTITY: Symmetry = Symmetry(0);
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry(0),
+        Symmetry(1),
+        Symmetry(2),
+        Symmetry(3),
+        Symmetry(4),
+        Symmetry(5),
+        Symmetry(6),
+        Symmetry(7),
+    ];
+
+    // 0 identity, 1-3 rotations by 90, 180 and 270 degrees, 4 and 5 mirror
+    // left-right and top-bottom, 6 and 7 mirror along the diagonals.
+    //#[inline]
+    const fn apply(self, row: usize, col: usize, size: usize) -> (usize, usize) {
+        let last = size - 1;
+        match self.0 {
+            0 => (row, col),
+            1 => (col, last - row),
+            2 => (last - row, last - col),
+            3 => (last - col, row),
+            4 => (row, last - col),
+            5 => (last - row, col),
+            6 => (col, row),
+            _ => (last - col, last - row),
+        }
+    }
+
+    pub const fn inverse(self) -> Symmetry {
+        match self.0 {
+            1 => Symmetry(3),
+            3 => Symmetry(1),
+            other => Symmetry(other),
+        }
+    }
+
+    //#[inline]
+    pub const fn board(self, global: usize) -> usize {
+        let (row, col) = self.apply(global / 3, global % 3, 3);
+        row * 3 + col
+    }
+
+    // The action at the same place after transforming the board.
+    //#[inline]
+    pub fn action(self, action: Action) -> Action {
+        Action::from_index(self.index(action.index())).unwrap()
+    }
+
+    // Same as `action` on dense action indices.
+    //#[inline]
+    pub const fn index(self, index: usize) -> usize {
+        INDEX_MAPS[self.0 as usize][index]
+    }
+}
+
+const INDEX_MAPS: [[usize; ACTION_COUNT]; 8] = index_maps();
+
+const fn index_maps() -> [[usize; ACTION_COUNT]; 8] {
+    let mut maps = [[0; ACTION_COUNT]; 8];
+    let mut symmetry = 0;
+    while symmetry < 8 {
+        let mut index = 0;
+        while index < ACTION_COUNT {
+            let (global, square) = (index / 9, index % 9);
+            let row = global / 3 * 3 + square / 3;
+            let col = global % 3 * 3 + square % 3;
+            let (row, col) = Symmetry(symmetry as u8).apply(row, col, 9);
+            maps[symmetry][index] = (row / 3 * 3 + col / 3) * 9 + row % 3 * 3 + col % 3;
+            index += 1;
+        }
+        symmetry += 1;
+    }
+    maps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symmetries_permute_the_squares() {
+        for symmetry in Symmetry::ALL {
+            let mut images = (0..ACTION_COUNT).map(|index| symmetry.index(index)).collect::<Vec<_>>();
+            images.sort_unstable();
+            assert_eq!(images, (0..ACTION_COUNT).collect::<Vec<_>>(), "{:?}", symmetry);
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_the_symmetry() {
+        for symmetry in Symmetry::ALL {
+            for index in 0..ACTION_COUNT {
+                assert_eq!(symmetry.inverse().index(symmetry.index(index)), index, "{:?}", symmetry);
+            }
+        }
+    }
+
+    // The board of a square and the board it sends the opponent to follow
+    // the symmetry, which keeps the forced board rule intact.
+    #[test]
+    fn boards_follow_the_squares() {
+        for symmetry in Symmetry::ALL {
+            for index in 0..ACTION_COUNT {
+                let (global, square) = (index / 9, index % 9);
+                let image = symmetry.index(index);
+                assert_eq!(image / 9, symmetry.board(global));
+                assert_eq!(image % 9, symmetry.board(square));
+            }
+        }
+    }
+
+    #[test]
+    fn identity_changes_nothing() {
+        for index in 0..ACTION_COUNT {
+            let action = Action::from_index(index).unwrap();
+            assert_eq!(Symmetry::IDENTITY.action(action), action);
+        }
+        assert_eq!(Symmetry::ALL[0], Symmetry::IDENTITY);
+    }
+}
@@ -0,0 +1,19 @@
+use super::cg_rand::Rng;
+use super::mcts::traits::GameState;
+
+use super::game_action::Action;
+use super::game_state::State;
+
+// The moves of a random game from `start` to its end, the same for the same
+// seed.
+pub fn random_game(seed: u64, start: &State) -> Vec<Action> {
+    let mut rng: Box<Rng> = Box::new(Rng::with_seed(seed));
+    let mut state = start.clone();
+    let mut moves = Vec::new();
+    while state.playable() {
+        let action = state.random_move(&mut rng).unwrap();
+        moves.push(action);
+        state = state.perform_action_copy(&action);
+    }
+    moves
+}
@@ -0,0 +1,63 @@
+use super::game_action::ACTION_COUNT;
+
+// Random keys for Zobrist hashing, generated at compile time with splitmix64
+// so every build hashes positions the same way.
+pub const SQUARE_KEYS: [[u64; ACTION_COUNT]; 2] = square_keys();
+// the player to move is X
+pub const SIDE_KEY: u64 = splitmix64(0x5eed_0001).1;
+// one key per forced board, none when free to choose
+pub const FORCED_KEYS: [u64; 9] = forced_keys();
+
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+const fn square_keys() -> [[u64; ACTION_COUNT]; 2] {
+    let mut keys = [[0; ACTION_COUNT]; 2];
+    let mut state = 0x5eed_0000;
+    let mut player = 0;
+    while player < 2 {
+        let mut index = 0;
+        while index < ACTION_COUNT {
+            let (next, key) = splitmix64(state);
+            keys[player][index] = key;
+            state = next;
+            index += 1;
+        }
+        player += 1;
+    }
+    keys
+}
+
+const fn forced_keys() -> [u64; 9] {
+    let mut keys = [0; 9];
+    let mut state = 0x5eed_0002;
+    let mut global = 0;
+    while global < 9 {
+        let (next, key) = splitmix64(state);
+        keys[global] = key;
+        state = next;
+        global += 1;
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_distinct() {
+        let mut keys = SQUARE_KEYS.iter().flatten().chain(&FORCED_KEYS).copied().collect::<Vec<_>>();
+        keys.push(SIDE_KEY);
+        let count = keys.len();
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), count);
+        assert!(!keys.contains(&0));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod node;
+pub mod tree;
+
+pub use super::*;
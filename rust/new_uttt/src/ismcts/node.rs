@@ -0,0 +1,189 @@
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::rc::Weak;
+
+use super::cg_rand::Rng;
+use super::mcts::traits::*;
+
+pub const C: f64 = 0.7;
+
+// Single-observer ISMCTS node. Nodes only hold the action leading to them, the
+// state is rebuilt from a fresh determinization on every iteration.
+pub struct IsMctsNode<P, R, A>
+where
+    P: GamePlayer<R> + Clone,
+    R: GameResult,
+    A: GameAction,
+{
+    pub action: Option<A>,
+    pub player: Option<P>,
+
+    parent: Weak<IsMctsNode<P, R, A>>,
+    #[allow(clippy::type_complexity)]
+    pub children: RefCell<HashMap<A, Rc<IsMctsNode<P, R, A>>>>,
+
+    pub wins: Cell<f64>,
+    pub visits: Cell<f64>,
+    pub availability: Cell<f64>,
+
+    result: PhantomData<R>,
+}
+
+impl<P, R, A> IsMctsNode<P, R, A>
+where
+    P: GamePlayer<R> + Clone,
+    R: GameResult,
+    A: GameAction,
+{
+    pub fn new() -> Self {
+        IsMctsNode {
+            action: None,
+            player: None,
+            parent: Weak::new(),
+            children: RefCell::new(HashMap::new()),
+            wins: Cell::new(0.),
+            visits: Cell::new(0.),
+            availability: Cell::new(1.),
+            result: PhantomData,
+        }
+    }
+
+    pub fn create_child(action: A, player: P, parent: Weak<IsMctsNode<P, R, A>>) -> Self {
+        IsMctsNode {
+            action: Some(action),
+            player: Some(player),
+            parent,
+            children: RefCell::new(HashMap::new()),
+            wins: Cell::new(0.),
+            visits: Cell::new(0.),
+            availability: Cell::new(1.),
+            result: PhantomData,
+        }
+    }
+
+    pub fn most_visited_child(&self) -> Rc<IsMctsNode<P, R, A>> {
+        let children = self.children.borrow();
+        if !children.is_empty() {
+            children
+                .values()
+                .reduce(|acc, node| {
+                    if acc.visits.get() >= node.visits.get() {
+                        acc
+                    } else {
+                        node
+                    }
+                })
+                .unwrap()
+                .clone()
+        } else {
+            panic!("There is no best move")
+        }
+    }
+
+    pub fn ucb(&self) -> f64 {
+        self.wins.get() / self.visits.get()
+            + C * (self.availability.get().ln() / self.visits.get()).sqrt()
+    }
+
+    // Walks down the tree using only the actions that are legal in `state`,
+    // expanding one untried action when there is one. `state` is advanced along
+    // the way and ends up as the state of the returned node.
+    pub fn select<S>(
+        self_ref: Rc<IsMctsNode<P, R, A>>,
+        state: &mut S,
+        rng: &mut Box<Rng>,
+    ) -> Rc<IsMctsNode<P, R, A>>
+    where
+        S: GameState<P, R, A>,
+    {
+        let mut node = self_ref;
+        while state.playable() {
+            let actions = state.possible_actions();
+
+            let children = node.children.borrow();
+            let untried = actions
+                .iter()
+                .filter(|action| !children.contains_key(action))
+                .collect::<Vec<_>>();
+
+            if let Some(&action) = rng.choice(untried.iter()) {
+                drop(children);
+                *state = state.perform_action_copy(action);
+
+                let child = Rc::new(IsMctsNode::create_child(
+                    action.clone(),
+                    state.current_player(),
+                    Rc::downgrade(&node),
+                ));
+                node.children
+                    .borrow_mut()
+                    .insert(action.clone(), Rc::clone(&child));
+                return child;
+            }
+
+            let selection = actions
+                .iter()
+                .filter_map(|action| children.get(action))
+                .inspect(|child| child.availability.set(child.availability.get() + 1.))
+                .reduce(|acc, child| if acc.ucb() >= child.ucb() { acc } else { child })
+                .unwrap()
+                .clone();
+            drop(children);
+
+            *state = state.perform_action_copy(selection.action.as_ref().unwrap());
+            node = selection;
+        }
+        node
+    }
+
+    pub fn backpropagate(&self, result: &R) {
+        self.visits.set(self.visits.get() + 1.);
+        if let Some(player) = &self.player {
+            self.wins.set(self.wins.get() + player.reward(result));
+        }
+        if let Some(parent) = self.parent.upgrade() {
+            parent.backpropagate(result);
+        }
+    }
+}
+
+impl<P, R, A> Default for IsMctsNode<P, R, A>
+where
+    P: GamePlayer<R> + Clone,
+    R: GameResult,
+    A: GameAction,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game::game_action::Action;
+    use super::super::game::game_state::{State, UTTTResult};
+    use super::super::game::player::Player;
+    use super::*;
+
+    // Every selection from a node that still has untried actions adds one of
+    // them, and leaves the state after it.
+    #[test]
+    fn select_tries_every_action_once() {
+        let root: Rc<IsMctsNode<Player, UTTTResult, Action>> = Rc::new(IsMctsNode::new());
+        let state = State::default().perform_action_copy(&Action::from_row_col(4, 4).unwrap());
+        let actions = state.possible_actions();
+        let mut rng: Box<Rng> = Box::new(Rng::with_seed(3));
+        for tried in 1..=actions.len() {
+            let mut determinization = state.clone();
+            let child = IsMctsNode::select(Rc::clone(&root), &mut determinization, &mut rng);
+            let action = child.action.unwrap();
+            assert!(actions.contains(&action));
+            assert_eq!(determinization.zobrist(), state.perform_action_copy(&action).zobrist());
+            assert_eq!(child.player, Some(state.next_player()));
+            assert_eq!(root.children.borrow().len(), tried);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{self, Instant};
+
+use super::cg_rand::Rng;
+use super::mcts::traits::*;
+
+use super::node::IsMctsNode;
+
+pub struct IsMctsTree<P, S, R, A>
+where
+    P: GamePlayer<R> + Clone,
+    S: Determinize<P, R, A>,
+    R: GameResult,
+    A: GameAction,
+{
+    pub root: RefCell<Rc<IsMctsNode<P, R, A>>>,
+    pub state: RefCell<S>,
+    pub observer: P,
+}
+
+impl<P, S, R, A> IsMctsTree<P, S, R, A>
+where
+    P: GamePlayer<R> + Clone,
+    S: Determinize<P, R, A>,
+    R: GameResult,
+    A: GameAction,
+{
+    pub fn new(state: S, observer: P) -> Self {
+        IsMctsTree {
+            root: RefCell::new(Rc::new(IsMctsNode::new())),
+            state: RefCell::new(state),
+            observer,
+        }
+    }
+
+    pub fn best_action(&self) -> A {
+        let child = self.root.borrow().most_visited_child();
+        child.action.clone().unwrap()
+    }
+
+    pub fn expand_tree(&self, begin: Instant, duration: time::Duration, rng: &mut Box<Rng>) {
+        let mut count = 0_u32;
+
+        let root_ref = Rc::clone(&self.root.borrow());
+        let state = self.state.borrow();
+        while begin.elapsed() < duration {
+            let mut determinization = state.determinize(&self.observer, rng);
+            let selected = IsMctsNode::select(Rc::clone(&root_ref), &mut determinization, rng);
+            if determinization.playable() {
+                let result = determinization.simulate_game(rng);
+                selected.backpropagate(&result);
+            } else {
+                selected.backpropagate(&determinization.outcome());
+            }
+            count += 1;
+        }
+        eprintln!("{}", count);
+    }
+
+    pub fn move_down(&self, action: A) {
+        let child = self.root.borrow().children.borrow().get(&action).cloned();
+        let next = child.unwrap_or_else(|| Rc::new(IsMctsNode::new()));
+        self.root.replace(next);
+
+        let state = self.state.borrow().perform_action_copy(&action);
+        self.state.replace(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game::game_action::Action;
+    use super::super::game::game_state::{State, UTTTResult};
+    use super::super::game::player::Player;
+    use super::super::game::test_util::random_game;
+    use super::*;
+
+    #[test]
+    fn search_finds_a_legal_action() {
+        let mut state = State::default();
+        for action in &random_game(5, &state)[..20] {
+            state = state.perform_action_copy(action);
+        }
+        let tree: IsMctsTree<Player, State, UTTTResult, Action> = IsMctsTree::new(state.clone(), state.next_player());
+        let mut rng: Box<Rng> = Box::new(Rng::with_seed(5));
+        tree.expand_tree(Instant::now(), time::Duration::from_millis(50), &mut rng);
+
+        let root = Rc::clone(&tree.root.borrow());
+        let children = root.children.borrow();
+        assert_eq!(children.len(), state.possible_actions().len());
+        let visits = children.values().map(|child| child.visits.get()).sum::<f64>();
+        assert_eq!(visits, root.visits.get());
+        assert!(root.visits.get() > 0.);
+
+        let best = tree.best_action();
+        assert!(state.possible_actions().contains(&best));
+        let best_visits = children[&best].visits.get();
+        drop(children);
+        tree.move_down(best);
+        assert_eq!(tree.root.borrow().visits.get(), best_visits);
+        assert_eq!(tree.state.borrow().zobrist(), state.perform_action_copy(&best).zobrist());
+    }
+}
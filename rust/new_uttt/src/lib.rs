@@ -1,3 +1,9 @@
 pub mod game;
 pub mod mcts;
-pub mod cg_rand;
\ No newline at end of file
+pub mod ismcts;
+pub mod ab;
+pub mod pns;
+pub mod book;
+pub mod arena;
+pub mod cg_rand;
+pub mod codingame;
\ No newline at end of file
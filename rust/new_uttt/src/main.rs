@@ -1,31 +1,287 @@
 extern crate new_uttt;
 
-use std::io;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::time;
 
 use new_uttt::game;
 use new_uttt::mcts;
 use new_uttt::cg_rand;
+use new_uttt::pns;
+use new_uttt::arena::bot::{AlphaBetaBot, Contestant, MctsBot, RandomBot};
+use new_uttt::arena::play::{play_match, MatchConfig};
+use new_uttt::book::opening_book::OpeningBook;
+use new_uttt::codingame::bot::{Bot, Driver};
+use new_uttt::codingame::input::{InputError, InputReader, TurnInput, START_ENV};
+use new_uttt::codingame::safety::Responder;
+use new_uttt::codingame::time_manager::{Phase, TimeManager};
+use new_uttt::codingame::transcript;
 
 use game::game_action::Action;
-use game::game_state::State;
-use game::masks::LOCAL_MOVES;
-use mcts::tree::MctsTree;
+use game::game_state::{State, UTTTResult};
+use game::record::GameRecord;
+use game::player::Player;
+use mcts::tree::{MctsTree, RootStrategy};
 use cg_rand::Rng;
 use mcts::traits::GameState;
 use mcts::node::MctsNode;
+use pns::solver::{PnSolver, Proof};
+
+const SOLVER_THRESHOLD: usize = 28;
+// strongest level of the terminal play mode
+const MAX_LEVEL: usize = 3;
+const OPENING_SQUARES: usize = 65;
+// relative search time of the opening, middlegame and endgame
+const PHASE_WEIGHTS: [f64; 3] = [0.8, 1., 1.];
+// the watchdog answers this long before the referee limit
+const WATCHDOG_MARGIN: time::Duration = time::Duration::from_millis(2);
 
 fn main() {
-    codingame();
+    let args = std::env::args().collect::<Vec<_>>();
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("bench-root") => {
+            let games = args.get(2).and_then(|games| games.parse().ok()).unwrap_or(20);
+            let record_dir = args.get(3).map(PathBuf::from);
+            for gumbel in [None, Some(16)] {
+                let strategy = RootStrategy::SequentialHalving { gumbel };
+                bench_root_strategy(strategy, games, record_dir.as_deref());
+            }
+        }
+        Some("replay") => match args.get(2) {
+            Some(path) => replay(Path::new(path)),
+            None => eprintln!("usage: new_uttt replay <record>"),
+        },
+        Some("import") => match args.get(2) {
+            Some(path) => import(Path::new(path), args.get(3).map(Path::new)),
+            None => eprintln!("usage: new_uttt import <transcript> [record]"),
+        },
+        Some("play") => play_human(&args[2..]),
+        _ => {
+            let mut options = Options::default();
+            for arg in &args[1..] {
+                match arg.as_str() {
+                    // local matches share the CPUs with the opponent
+                    "--no-ponder" => options.ponder = false,
+                    "--no-rebuild" => options.rebuild = false,
+                    _ => {
+                        eprintln!("usage: new_uttt [--no-ponder] [--no-rebuild]");
+                        return;
+                    }
+                }
+            }
+            codingame(options)
+        }
+    }
+}
+
+// Plays `games` games of `strategy` against plain UCT, alternating who starts.
+// The games are saved to `record_dir` when given.
+fn bench_root_strategy(strategy: RootStrategy, games: usize, record_dir: Option<&Path>) {
+    let duration = time::Duration::new(0, 99000000);
+    let config = MatchConfig {
+        games,
+        first_turn: duration,
+        turn: duration,
+        grace: None,
+        ..MatchConfig::default()
+    };
+    let mut challenger = MctsBot::new(strategy);
+    let result = play_match(&mut challenger, &mut MctsBot::new(RootStrategy::Uct), &config);
+
+    if let Some(dir) = record_dir {
+        for (game, record) in result.records.iter().enumerate() {
+            let path = dir.join(format!("{}-{}.txt", challenger.name(), game));
+            if let Err(error) = fs::write(&path, record.to_string()) {
+                eprintln!("cannot write {}: {}", path.display(), error);
+            }
+        }
+    }
+    println!("{:?} vs Uct: +{} ={} -{}", strategy, result.wins, result.draws, result.losses);
+}
+
+// A person against the engine in the terminal. The engine's search prints
+// to stderr, redirect it to keep the board readable.
+fn play_human(args: &[String]) {
+    let mut thinking = time::Duration::from_millis(1000);
+    let mut level = MAX_LEVEL;
+    let mut human_first = true;
+    let mut rest = args;
+    while !rest.is_empty() {
+        let valid = match rest {
+            [option, value, tail @ ..] => {
+                rest = tail;
+                match (option.as_str(), value.as_str()) {
+                    ("--time", ms) => match ms.parse() {
+                        Ok(ms) => {
+                            thinking = time::Duration::from_millis(ms);
+                            true
+                        }
+                        Err(_) => false,
+                    },
+                    ("--level", n) => match n.parse() {
+                        Ok(n) if n <= MAX_LEVEL => {
+                            level = n;
+                            true
+                        }
+                        _ => false,
+                    },
+                    ("--first", "me") => {
+                        human_first = true;
+                        true
+                    }
+                    ("--first", "bot") => {
+                        human_first = false;
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            // an option without its value
+            _ => false,
+        };
+        if !valid {
+            eprintln!("usage: new_uttt play [--time ms] [--level 0-{}] [--first me|bot]", MAX_LEVEL);
+            return;
+        }
+    }
+
+    let mut engine = engine(level);
+    let mut rng: Box<Rng> = Box::new(cg_rand::Rng::new());
+    let mut state = State::default();
+    engine.new_game(&state);
+    let human = if human_first { state.next_player() } else { state.next_player().other() };
+    println!("you play {:?} against {}, enter moves as \"row col\" from 0 to 8", human, engine.name());
+
+    let mut lines = io::stdin().lock().lines();
+    while state.playable() {
+        print!("\n{}", state.render());
+        let action = if state.next_player() == human {
+            print!("your move: ");
+            let _ = io::stdout().flush();
+            let line = match lines.next() {
+                Some(Ok(line)) => line,
+                _ => return,
+            };
+            match human_move(&state, &line) {
+                Ok(action) => action,
+                Err(reason) => {
+                    println!("{}", reason);
+                    continue;
+                }
+            }
+        } else {
+            let action = engine.choose(&state, time::Instant::now() + thinking, &mut rng);
+            println!("{} plays {}", engine.name(), action);
+            action
+        };
+        engine.observe(action);
+        state = state.perform_action_copy(&action);
+    }
+    print!("\n{}", state.render());
+}
+
+// Strength levels of `play_human`, weakest first.
+fn engine(level: usize) -> Box<dyn Contestant> {
+    match level {
+        0 => Box::new(RandomBot),
+        1 => Box::new(AlphaBetaBot::new(2)),
+        2 => Box::new(AlphaBetaBot::new(6)),
+        _ => Box::new(MctsBot::new(RootStrategy::Uct)),
+    }
 }
+
+// The move typed by the player, or why it can't be played.
+fn human_move(state: &State, line: &str) -> Result<Action, String> {
+    let action = line.trim().parse::<Action>().map_err(|error| error.to_string())?;
+    let (row, col) = action.to_row_col();
+    let global = action.global();
+    if state.square(row, col).is_some() {
+        return Err(format!("square {} {} is already taken", row, col));
+    }
+    match state.forced_board() {
+        Some(forced) if forced != global => Err(format!(
+            "the last move sends you to board {}, rows {}-{} and columns {}-{}",
+            forced,
+            forced / 3 * 3,
+            forced / 3 * 3 + 2,
+            forced % 3 * 3,
+            forced % 3 * 3 + 2
+        )),
+        None if !state.global_states.in_play(global) => {
+            Err(format!("board {} is already decided, pick a square in another one", global))
+        }
+        _ => Ok(action),
+    }
+}
+
+// Prints every position of a recorded game, to look into a bad move.
+fn replay(path: &Path) {
+    let record = match fs::read_to_string(path).map_err(|error| error.to_string()).and_then(|text| {
+        text.parse::<GameRecord>().map_err(|error| error.to_string())
+    }) {
+        Ok(record) => record,
+        Err(error) => {
+            eprintln!("cannot read {}: {}", path.display(), error);
+            return;
+        }
+    };
+
+    println!("{} vs {}", record.first, record.second);
+    let states = match record.replay() {
+        Ok(states) => states,
+        Err(error) => {
+            eprintln!("{}", error);
+            return;
+        }
+    };
+    println!("{}", states[0].render());
+    for (ply, (recorded, state)) in record.moves.iter().zip(&states[1..]).enumerate() {
+        match recorded.time {
+            Some(time) => println!("{}. {} ({:?})", ply + 1, recorded.action, time),
+            None => println!("{}. {}", ply + 1, recorded.action),
+        }
+        println!("{}", state.render());
+    }
+}
+
+// Turns the referee input of a Codingame game into a record, written to
+// `output` or printed, followed by the final position.
+fn import(path: &Path, output: Option<&Path>) {
+    let record = match fs::read_to_string(path).map_err(|error| error.to_string()).and_then(|text| {
+        transcript::import(&text, "me", "opponent").map_err(|error| error.to_string())
+    }) {
+        Ok(record) => record,
+        Err(error) => {
+            eprintln!("cannot import {}: {}", path.display(), error);
+            return;
+        }
+    };
+
+    match output {
+        Some(output) => {
+            if let Err(error) = fs::write(output, record.to_string()) {
+                eprintln!("cannot write {}: {}", output.display(), error);
+            }
+        }
+        None => print!("{}", record),
+    }
+    if let Ok(states) = record.replay() {
+        let last = states.last().unwrap();
+        eprintln!("{}{}", last.render(), last);
+    }
+}
+
+#[allow(dead_code)]
 fn perf_test() {
     let mut rng = Box::new(cg_rand::Rng::new());
 
     let mut game = State::default();
     let mcts = MctsTree::new(game);
 
-    let mut action = Action::from_coords(4, LOCAL_MOVES[4]);
+    // the center of the center board
+    let mut action = Action::from_index(40).unwrap();
 
     let root_ref = mcts.root.borrow();
     let state = root_ref.state.perform_action_copy(&action);    
@@ -72,89 +328,334 @@ fn perf_test() {
     }
 }
 
+// Switches of the Codingame mode, all on by default.
+struct Options {
+    // search on the opponent's time
+    ponder: bool,
+    // after a desync, search a fresh tree of the referee's moves only
+    rebuild: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            ponder: true,
+            rebuild: true,
+        }
+    }
+}
+
 //#[inline]
-fn codingame() {
-    let mut rng: Box<Rng> = Box::new(cg_rand::Rng::new());
-    let inputs = read_input();
+fn codingame(options: Options) {
+    let mut time_manager = TimeManager::new(
+        time::Duration::from_millis(1000),
+        time::Duration::from_millis(100),
+        time::Duration::from_millis(5),
+    );
+    time_manager.phase_weights = PHASE_WEIGHTS;
 
-    let game = State::default();
-    let mcts = MctsTree::new(game);
-    let mut action: Action;
+    Driver::new(time_manager, WATCHDOG_MARGIN).run(&mut UtttBot::new(options));
+}
 
-    let first_turn_begin = time::Instant::now();
-    let first_duration = time::Duration::new(0, 999999995);
-    if inputs.0 < 0 {
-        action = Action::from_coords(4, LOCAL_MOVES[4]);
-
-        let root_ref = mcts.root.borrow();
-        let state = root_ref.state.perform_action_copy(&action);    
-        let next = Rc::new(MctsNode::create_child(state, Rc::downgrade(&root_ref)));  
-        drop(root_ref);   
-        mcts.root.replace(next);
-
-        mcts.expand_tree(first_turn_begin, first_duration, &mut rng);
-        println!("4 4");
-    } else {
-        let opponent_row = inputs.0;
-        let opponent_col = inputs.1;
-
-        action = Action::from_coords(
-            (opponent_col / 3 + (opponent_row / 3) * 3) as usize,
-            LOCAL_MOVES[(opponent_col % 3 + (opponent_row % 3) * 3) as usize],
-        );
-
-        let root_ref = mcts.root.borrow();
-        let state = root_ref.state.perform_action_copy(&action);    
-        let next = Rc::new(MctsNode::create_child(state, Rc::downgrade(&root_ref)));  
-        drop(root_ref);   
-        mcts.root.replace(next);
-
-        mcts.expand_tree(first_turn_begin, first_duration, &mut rng);
-
-        let child = mcts.root.borrow().best_child();
-        action = child.state.last_action.unwrap();
-        mcts.root.replace(child);
+struct UtttBot {
+    options: Options,
+    rng: Box<Rng>,
+    mcts: MctsTree<Player, State, UTTTResult, Action>,
+    solver: PnSolver<Player, State, UTTTResult, Action>,
+    book: OpeningBook,
+    // our state disagrees with the referee's valid actions this turn
+    desynced: bool,
+}
+
+impl UtttBot {
+    fn new(options: Options) -> Self {
+        UtttBot {
+            options,
+            rng: Box::new(cg_rand::Rng::new()),
+            mcts: MctsTree::new(start_state()),
+            solver: PnSolver::new(),
+            book: OpeningBook::embedded(),
+            desynced: false,
+        }
+    }
+
+    fn book_move(&self) -> Option<Action> {
+        let state = self.mcts.root.borrow().state.clone();
+        self.book.best(&state).filter(|action| state.possible_actions().contains(action))
+    }
+}
+
+impl Bot for UtttBot {
+    type Init = ();
+    type Input = TurnInput;
+    type Output = Action;
+
+    fn read_init<B: BufRead>(_input: &mut InputReader<B>) -> Result<(), InputError> {
+        Ok(())
+    }
 
+    fn read_turn<B: BufRead>(input: &mut InputReader<B>) -> Result<TurnInput, InputError> {
+        input.read_turn()
+    }
+
+    fn write(action: &Action) {
         action.print();
     }
 
-    let turn_duration = time::Duration::new(0, 99999900);
-    // game loop
-    loop {
+    fn init(&mut self, _init: (), responder: &Responder<Action>) {
+        self.mcts.early_stop = true;
+        let responder = responder.clone();
+        self.mcts.progress = Some(Box::new(move |&action| responder.update_fallback(action)));
+    }
+
+    fn observe(&mut self, turn: &TurnInput) -> Phase {
+        if let Some((row, col)) = turn.opponent {
+            // the first move we are told about from a start position is the
+            // last one of its opening, already on the board
+            let played = self.mcts.root.borrow().state.square(row, col).is_some();
+            if !played {
+                self.mcts.move_down(to_action((row, col)));
+            }
+        }
+
+        let state = self.mcts.root.borrow().state.clone();
+        self.desynced = !in_sync(&state, &turn.valid_actions);
+        if self.desynced {
+            if self.options.rebuild {
+                restrict_to_referee(&self.mcts, &turn.valid_actions);
+            }
+        } else {
+            // equivalent moves only need to be searched once
+            let classes = state.equivalent_actions();
+            if classes.len() < state.possible_actions().len() {
+                self.mcts.collapse_root(&classes);
+            }
+        }
+        phase(&self.mcts.root.borrow().state)
+    }
+
+    fn fallback(&mut self, turn: &TurnInput) -> Action {
+        match self.book_move() {
+            Some(action) => action,
+            None => fallback_action(&self.mcts, &turn.valid_actions, &mut self.rng),
+        }
+    }
+
+    // the referee lists at least one action every turn
+    fn first_legal(turn: &TurnInput) -> Action {
+        to_action(turn.valid_actions[0])
+    }
+
+    // Book moves are played at once, the turn is then spent pondering on the
+    // replies.
+    fn turn(&mut self, _turn: TurnInput, deadline: time::Instant) -> Action {
+        if let Some(action) = self.book_move() {
+            return action;
+        }
+        // the solver would prove moves of a position the referee disagrees with
+        let solver = if self.desynced { None } else { Some(&self.solver) };
+        decide(&self.mcts, solver, deadline, &mut self.rng)
+    }
+
+    fn answered(&mut self, action: Action) {
+        if self.mcts.root.borrow().state.last_action != Some(action) {
+            self.mcts.move_down(action);
+        }
+    }
+
+    fn recover(&mut self) {
+        let state = self.mcts.root.borrow().state.clone();
+        self.mcts.reset(state);
+    }
+
+    fn ponder<F: FnMut() -> bool>(&mut self, stop: F) {
+        if self.options.ponder {
+            self.mcts.ponder(&mut self.rng, stop);
+        } else {
+            self.mcts.collect_garbage();
+        }
+    }
+}
+
+// Compares our legal moves with the ones the referee sent, logging the board
+// on a mismatch.
+fn in_sync(state: &State, valid_actions: &[(usize, usize)]) -> bool {
+    if valid_actions.is_empty() {
+        return true;
+    }
+    let ours = state.possible_actions().into_iter().map(Action::to_row_col).collect::<Vec<_>>();
+    let missing = valid_actions.iter().filter(|action| !ours.contains(action)).collect::<Vec<_>>();
+    let unexpected = ours.iter().filter(|action| !valid_actions.contains(action)).collect::<Vec<_>>();
+    if missing.is_empty() && unexpected.is_empty() {
+        return true;
+    }
+
+    eprintln!("desync: referee only {:?}, we only {:?}", missing, unexpected);
+    eprintln!("{}{}", state.render(), state);
+    false
+}
+
+// The position the referee starts the game from, the empty board unless a
+// local referee says otherwise.
+fn start_state() -> State {
+    match std::env::var(START_ENV) {
+        Ok(notation) => notation.parse().unwrap_or_else(|error| {
+            eprintln!("ignoring {}: {}", START_ENV, error);
+            State::default()
+        }),
+        Err(_) => State::default(),
+    }
+}
+
+// Starts a fresh tree that only searches the referee's moves, the statistics
+// of the old one were gathered over moves that may not exist.
+fn restrict_to_referee(
+    mcts: &MctsTree<Player, State, UTTTResult, Action>,
+    valid_actions: &[(usize, usize)],
+) {
+    let state = mcts.root.borrow().state.clone();
+    mcts.reset(state);
+    let allowed = valid_actions.iter().map(|&coords| to_action(coords)).collect::<Vec<_>>();
+    mcts.restrict_root(&allowed);
+}
+
+// The input reader already rejects squares off the board.
+fn to_action((row, col): (usize, usize)) -> Action {
+    Action::from_row_col(row, col).expect("coordinates checked by the input reader")
+}
+
+// The solver gets half of the time left, the search whatever remains of it.
+fn decide(
+    mcts: &MctsTree<Player, State, UTTTResult, Action>,
+    solver: Option<&PnSolver<Player, State, UTTTResult, Action>>,
+    deadline: time::Instant,
+    rng: &mut Box<Rng>,
+) -> Action {
+    let state = mcts.root.borrow().state.clone();
+    if let Some(solver) = solver.filter(|_| state.empty_squares() <= SOLVER_THRESHOLD) {
         let begin = time::Instant::now();
-        let inputs = read_input();
-        let opponent_row = inputs.0;
-        let opponent_col = inputs.1;
-        let opp_move = Action::from_coords(
-            (opponent_col / 3 + (opponent_row / 3) * 3) as usize,
-            LOCAL_MOVES[(opponent_col % 3 + (opponent_row % 3) * 3) as usize],
-        );
+        match solver.solve(&state, begin, deadline.saturating_duration_since(begin) / 2) {
+            Proof::Won(action) | Proof::Drawn(action) => return action,
+            Proof::Lost | Proof::Unknown => {}
+        }
+    }
 
-        mcts.move_down(opp_move);
-        mcts.expand_tree(begin, turn_duration, &mut rng);
+    let begin = time::Instant::now();
+    mcts.expand_tree(begin, deadline.saturating_duration_since(begin), rng);
+    match mcts.best_action() {
+        Some(action) => action,
+        // no time for a single iteration
+        None => state.random_move(rng).unwrap(),
+    }
+}
 
-        let child = mcts.best_child();
-        action = child.state.last_action.unwrap();
-        mcts.root.replace(child);
+// Best move of the pondered tree if there is one, otherwise any move the
+// referee allows.
+fn fallback_action(
+    mcts: &MctsTree<Player, State, UTTTResult, Action>,
+    valid_actions: &[(usize, usize)],
+    rng: &mut Box<Rng>,
+) -> Action {
+    match mcts.best_action() {
+        Some(action) => action,
+        None if !valid_actions.is_empty() => to_action(*rng.choice(valid_actions.iter()).unwrap()),
+        None => {
+            let state = mcts.root.borrow().state.clone();
+            let actions = state.possible_actions();
+            *rng.choice(actions.iter()).unwrap()
+        }
+    }
+}
 
-        action.print();
+fn phase(state: &State) -> Phase {
+    match state.empty_squares() {
+        empty if empty > OPENING_SQUARES => Phase::Opening,
+        empty if empty <= SOLVER_THRESHOLD => Phase::Endgame,
+        _ => Phase::Middlegame,
     }
 }
 
-//#[inline]
-fn read_input() -> (i8, i8) {
-    let mut input_line = String::new();
-    io::stdin().read_line(&mut input_line).unwrap();
-    let inputs = input_line.split_ascii_whitespace().collect::<Vec<_>>();
-    let mut input_line_2 = String::new();
-    io::stdin().read_line(&mut input_line_2).unwrap();
-    let valid_action_count = input_line_2.trim().parse::<i32>().unwrap();
-    for _ in 0..valid_action_count as usize {
-        io::stdin().read_line(&mut input_line_2).unwrap();
-    }
-
-    (
-        inputs[0].trim().parse::<i8>().unwrap(),
-        inputs[1].trim().parse::<i8>().unwrap()
-    )
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut actions: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+        actions.sort();
+        actions
+    }
+
+    fn root_actions(bot: &UtttBot) -> Vec<(usize, usize)> {
+        let root = bot.mcts.root.borrow();
+        let actions = root.children.borrow().keys().map(|&action| action.to_row_col()).collect();
+        sorted(actions)
+    }
+
+    // A searched tree without the referee's move, which sends us to the
+    // center board, then valid actions that mostly lie outside of it.
+    fn desync(options: Options) -> (UtttBot, Vec<(usize, usize)>) {
+        let mut bot = UtttBot::new(options);
+        bot.rng = Box::new(Rng::with_seed(1));
+        bot.mcts.reset(State::default());
+        bot.mcts.expand_iterations(5, &mut bot.rng);
+        let opponent = (0..9)
+            .flat_map(|row| (0..9).map(move |col| (row, col)))
+            .filter(|&(row, col)| row % 3 == 1 && col % 3 == 1)
+            .find(|square| !root_actions(&bot).contains(square))
+            .unwrap();
+
+        let valid_actions = vec![(0, 0), (0, 1), (3, 3), (8, 8)];
+        bot.observe(&TurnInput {
+            opponent: Some(opponent),
+            valid_actions: valid_actions.clone(),
+        });
+        assert!(bot.desynced);
+        bot.mcts.expand_iterations(200, &mut bot.rng);
+        (bot, valid_actions)
+    }
+
+    #[test]
+    fn desync_searches_the_referee_actions() {
+        let (bot, valid_actions) = desync(Options::default());
+        assert_eq!(root_actions(&bot), sorted(valid_actions));
+    }
+
+    #[test]
+    fn desync_keeps_the_tree_without_rebuild() {
+        let options = Options {
+            rebuild: false,
+            ..Options::default()
+        };
+        let (bot, _) = desync(options);
+        let state = bot.mcts.root.borrow().state.clone();
+        let ours = state.possible_actions().into_iter().map(Action::to_row_col).collect();
+        assert_eq!(root_actions(&bot), sorted(ours));
+    }
+
+    #[test]
+    fn human_moves_are_the_legal_ones() {
+        let mut rng: Box<Rng> = Box::new(Rng::with_seed(4));
+        for _ in 0..5 {
+            let mut state = State::default();
+            while state.playable() {
+                let legal = state.possible_actions();
+                for index in 0..81 {
+                    let action = Action::from_index(index).unwrap();
+                    let (row, col) = action.to_row_col();
+                    let answer = human_move(&state, &format!("{} {}\n", row, col));
+                    assert_eq!(answer.is_ok(), legal.contains(&action), "{} {} in {}", row, col, state);
+                }
+                state = state.perform_action_copy(&state.random_move(&mut rng).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn human_moves_explain_the_mistake() {
+        let state = State::default().perform_action_copy(&to_action((4, 4)));
+        assert!(human_move(&state, "4").is_err());
+        assert!(human_move(&state, "9 0").is_err());
+        assert!(human_move(&state, "4 4").unwrap_err().contains("already taken"));
+        assert!(human_move(&state, "0 0").unwrap_err().contains("rows 3-5 and columns 3-5"));
+        assert_eq!(human_move(&state, " 3 5 ").unwrap(), to_action((3, 5)));
+    }
 }
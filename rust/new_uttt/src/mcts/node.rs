@@ -18,6 +18,7 @@ where
     pub state: S,
 
     parent: Weak<MctsNode<P, S, R, A>>,
+    #[allow(clippy::type_complexity)]
     pub children: RefCell<HashMap<A, Rc<MctsNode<P, S, R, A>>>>,
 
     unvisited_actions: RefCell<Vec<A>>,
@@ -83,6 +84,28 @@ where
         }
     }
 
+    // Limits the actions of this node to `allowed`, adding the ones it did not
+    // know about. The removed children are returned so the caller decides when
+    // their subtrees are freed.
+    pub fn restrict_actions(&self, allowed: &[A]) -> Vec<Rc<MctsNode<P, S, R, A>>> {
+        let mut children = self.children.borrow_mut();
+        let mut unvisited_actions = self.unvisited_actions.borrow_mut();
+        let removed_actions = children.keys().filter(|action| !allowed.contains(action)).cloned().collect::<Vec<_>>();
+        let removed = removed_actions.iter().filter_map(|action| children.remove(action)).collect();
+        unvisited_actions.retain(|action| allowed.contains(action));
+        for action in allowed {
+            if !children.contains_key(action) && !unvisited_actions.contains(action) {
+                unvisited_actions.push(action.clone());
+            }
+        }
+        self.expanded.set(unvisited_actions.is_empty());
+        removed
+    }
+
+    pub fn fully_expanded(&self) -> bool {
+        self.unvisited_actions.borrow().is_empty()
+    }
+
     pub fn uct(&self, visits: f64) -> f64 {
         self.wins.get() / self.visits.get() - C * (self.visits.get() / visits)
     }
@@ -141,6 +164,17 @@ where
         }
     }
 
+    pub fn expand_all(self_ref: &Rc<MctsNode<P, S, R, A>>) {
+        let mut unvisited_actions = self_ref.unvisited_actions.borrow_mut();
+        let mut children = self_ref.children.borrow_mut();
+        for action in unvisited_actions.drain(..) {
+            let state = self_ref.state.perform_action_copy(&action);
+            let child = Rc::new(MctsNode::create_child(state, Rc::downgrade(self_ref)));
+            children.insert(action, child);
+        }
+        self_ref.expanded.set(true);
+    }
+
     pub fn backpropagate(&self, result: &R) {
         self.visits.set(self.visits.get() + 1_f64);
         self.wins
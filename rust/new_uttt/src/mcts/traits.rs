@@ -27,3 +27,13 @@ where
     fn outcome(&self) -> R;
     fn playable(&self) -> bool;
 }
+
+pub trait Determinize<P, R, A>: GameState<P, R, A>
+where
+    P: GamePlayer<R>,
+    R: GameResult,
+    A: GameAction,
+{
+    // Samples a perfect-information state consistent with what `observer` can see.
+    fn determinize(&self, observer: &P, rng: &mut Box<Rng>) -> Self;
+}
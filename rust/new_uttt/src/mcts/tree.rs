@@ -7,6 +7,23 @@ use super::traits::*;
 
 use super::node::MctsNode;
 
+const GUMBEL_C_VISIT: f64 = 50.;
+const GUMBEL_C_SCALE: f64 = 0.1;
+const EARLY_STOP_INTERVAL: u32 = 256;
+const PONDER_CHECK_INTERVAL: u32 = 16;
+const GARBAGE_STEP: usize = 256;
+const PROGRESS_INTERVAL: u32 = 1024;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RootStrategy {
+    Uct,
+    // Spreads the budget over the root actions in rounds, halving them after
+    // every round. With `gumbel: Some(candidates)` only that many actions
+    // sampled with Gumbel noise take part, and the noise also stays in the
+    // score used for halving.
+    SequentialHalving { gumbel: Option<usize> },
+}
+
 pub struct MctsTree<P, S, R, A>
 where
     P: GamePlayer<R>,
@@ -15,6 +32,15 @@ where
     A: GameAction,
 {
     pub root: RefCell<Rc<MctsNode<P, S, R, A>>>,
+    pub root_strategy: RootStrategy,
+    pub early_stop: bool,
+    // Called regularly during the search with the best root action so far.
+    #[allow(clippy::type_complexity)]
+    pub progress: Option<Box<dyn Fn(&A)>>,
+
+    halving_winner: RefCell<Option<A>>,
+    #[allow(clippy::type_complexity)]
+    garbage: RefCell<Vec<Rc<MctsNode<P, S, R, A>>>>,
 }
 
 impl<P, S, R, A> MctsTree<P, S, R, A>
@@ -28,33 +54,370 @@ where
         let node_ref = Rc::new(MctsNode::new(state));
         MctsTree {
             root: RefCell::new(node_ref),
+            root_strategy: RootStrategy::Uct,
+            early_stop: false,
+            progress: None,
+            halving_winner: RefCell::new(None),
+            garbage: RefCell::new(Vec::new()),
         }
     }
 
     pub fn best_child(&self) -> Rc<MctsNode<P, S, R, A>>{
-        self.root.borrow().best_child()
+        let root = self.root.borrow();
+        if let Some(action) = self.halving_winner.take() {
+            if let Some(child) = root.children.borrow().get(&action) {
+                return Rc::clone(child);
+            }
+        }
+        root.best_child()
+    }
+
+    pub fn best_action(&self) -> Option<A> {
+        let root = self.root.borrow();
+        let children = root.children.borrow();
+        children
+            .iter()
+            .map(|(action, child)| (action, child.wins.get() / child.visits.get()))
+            .reduce(|acc, entry| if acc.1 > entry.1 { acc } else { entry })
+            .map(|(action, _)| action.clone())
     }
 
     pub fn expand_tree(&self, begin: Instant, duration: time::Duration, rng: &mut Box<Rng>) {
+        match self.root_strategy {
+            RootStrategy::Uct => self.expand_uct(begin, duration, rng),
+            RootStrategy::SequentialHalving { gumbel } => {
+                self.expand_sequential_halving(begin, duration, rng, gumbel)
+            }
+        }
+    }
+
+    fn expand_uct(&self, begin: Instant, duration: time::Duration, rng: &mut Box<Rng>) {
         let mut count = 0_u32;
 
         let root_ref = Rc::clone(&self.root.borrow());
         while begin.elapsed() < duration {
-            let selected = MctsNode::select(Rc::clone(&root_ref));
-            let end_state = selected.state.clone();
-            if end_state.playable() {
-                let result = end_state.simulate_game(rng);
-                selected.backpropagate(&result);
-            } else {
-                selected.backpropagate(&end_state.outcome());
-            }
+            MctsTree::iterate(&root_ref, rng);
             count += 1;
+
+            if let Some(progress) = &self.progress {
+                if count.is_multiple_of(PROGRESS_INTERVAL) {
+                    if let Some(action) = self.best_action() {
+                        progress(&action);
+                    }
+                }
+            }
+            if self.early_stop
+                && count.is_multiple_of(EARLY_STOP_INTERVAL)
+                && MctsTree::decided(&root_ref, begin, duration, count)
+            {
+                eprintln!("early stop after {:?}", begin.elapsed());
+                break;
+            }
         }
         eprintln!("{}", count);
     }
 
+    // Runs a fixed number of UCT iterations, for searches that must not
+    // depend on the speed of the machine.
+    pub fn expand_iterations(&self, iterations: u32, rng: &mut Box<Rng>) {
+        let root_ref = Rc::clone(&self.root.borrow());
+        for _ in 0..iterations {
+            MctsTree::iterate(&root_ref, rng);
+        }
+    }
+
+    // True when the most visited root child is also the best scoring one and
+    // the runner-up cannot catch up on visits at the current iteration rate.
+    fn decided(root: &MctsNode<P, S, R, A>, begin: Instant, duration: time::Duration, count: u32) -> bool {
+        if !root.fully_expanded() {
+            return false;
+        }
+        let children = root.children.borrow();
+        if children.len() < 2 {
+            return true;
+        }
+
+        let elapsed = begin.elapsed().as_secs_f64();
+        let remaining = duration.as_secs_f64() - elapsed;
+        let remaining_iterations = count as f64 * remaining / elapsed;
+
+        let mut by_visits = children.values().collect::<Vec<_>>();
+        by_visits.sort_by(|a, b| b.visits.get().total_cmp(&a.visits.get()));
+        let (first, second) = (by_visits[0], by_visits[1]);
+
+        let score = |node: &MctsNode<P, S, R, A>| node.wins.get() / node.visits.get();
+        let best_scoring = children.values().all(|child| score(child) <= score(first));
+
+        best_scoring && first.visits.get() - second.visits.get() > remaining_iterations
+    }
+
+    fn expand_sequential_halving(
+        &self,
+        begin: Instant,
+        duration: time::Duration,
+        rng: &mut Box<Rng>,
+        gumbel: Option<usize>,
+    ) {
+        let mut count = 0_u32;
+        // a winner from an earlier search belongs to another position
+        self.halving_winner.replace(None);
+
+        let root_ref = Rc::clone(&self.root.borrow());
+        MctsNode::expand_all(&root_ref);
+
+        let mut remaining = root_ref
+            .children
+            .borrow()
+            .iter()
+            .map(|(action, child)| {
+                let noise = if gumbel.is_some() { -(-rng.f64().ln()).ln() } else { 0. };
+                (action.clone(), Rc::clone(child), noise)
+            })
+            .collect::<Vec<_>>();
+        if remaining.is_empty() {
+            return;
+        }
+
+        // without a policy every action has the same prior, so only the noise
+        // can pick the candidates, plain halving starts from all of them
+        if let Some(candidates) = gumbel {
+            remaining.sort_by(|a, b| b.2.total_cmp(&a.2));
+            remaining.truncate(candidates.max(1));
+        }
+
+        // the leader of the last finished round
+        let mut leader = None;
+        let mut rounds = remaining.len().next_power_of_two().trailing_zeros().max(1);
+        'search: while remaining.len() > 1 && begin.elapsed() < duration {
+            let round_end = begin.elapsed() + (duration.saturating_sub(begin.elapsed())) / rounds;
+            'round: loop {
+                for index in 0..remaining.len() {
+                    if begin.elapsed() >= round_end {
+                        break 'round;
+                    }
+                    MctsTree::iterate(&remaining[index].1, rng);
+                    count += 1;
+
+                    if let Some(progress) = &self.progress {
+                        if count.is_multiple_of(PROGRESS_INTERVAL) {
+                            if let Some(action) = leader.clone().or_else(|| self.best_action()) {
+                                progress(&action);
+                            }
+                        }
+                    }
+                    if self.early_stop
+                        && count.is_multiple_of(EARLY_STOP_INTERVAL)
+                        && MctsTree::halving_decided(&mut remaining, begin, duration, count)
+                    {
+                        eprintln!("early stop after {:?}", begin.elapsed());
+                        break 'search;
+                    }
+                }
+            }
+
+            MctsTree::sort_by_halving_score(&mut remaining, 0.);
+            remaining.truncate(remaining.len().div_ceil(2));
+            leader = Some(remaining[0].0.clone());
+            rounds = rounds.saturating_sub(1).max(1);
+        }
+
+        self.halving_winner.replace(Some(remaining[0].0.clone()));
+        eprintln!("{}", count);
+    }
+
+    // Best first by the score halving drops candidates with, as if every
+    // candidate had `extra_visits` more visits to come.
+    #[allow(clippy::type_complexity)]
+    fn sort_by_halving_score(remaining: &mut [(A, Rc<MctsNode<P, S, R, A>>, f64)], extra_visits: f64) {
+        let max_visits = remaining
+            .iter()
+            .map(|(_, child, _)| child.visits.get())
+            .fold(0., f64::max);
+        let score = |child: &MctsNode<P, S, R, A>, noise: f64| {
+            let q = child.wins.get() / child.visits.get();
+            noise + (GUMBEL_C_VISIT + max_visits + extra_visits) * GUMBEL_C_SCALE * q
+        };
+        remaining.sort_by(|a, b| score(&b.1, b.2).total_cmp(&score(&a.1, a.2)));
+    }
+
+    // True when no other candidate could overtake the leader even if it won
+    // every iteration left at the current rate and the leader lost all of
+    // its own. Only the leader is kept then.
+    #[allow(clippy::type_complexity)]
+    fn halving_decided(
+        remaining: &mut Vec<(A, Rc<MctsNode<P, S, R, A>>, f64)>,
+        begin: Instant,
+        duration: time::Duration,
+        count: u32,
+    ) -> bool {
+        let elapsed = begin.elapsed().as_secs_f64();
+        let remaining_iterations = count as f64 * (duration.as_secs_f64() - elapsed) / elapsed;
+
+        MctsTree::sort_by_halving_score(remaining, remaining_iterations);
+        let max_visits = remaining
+            .iter()
+            .map(|(_, child, _)| child.visits.get())
+            .fold(0., f64::max);
+        let scale = (GUMBEL_C_VISIT + max_visits + remaining_iterations) * GUMBEL_C_SCALE;
+        let (_, leader, leader_noise) = &remaining[0];
+        let worst = leader_noise + scale * leader.wins.get() / (leader.visits.get() + remaining_iterations);
+        let decided = remaining[1..].iter().all(|(_, child, noise)| {
+            let best = (child.wins.get() + remaining_iterations) / (child.visits.get() + remaining_iterations);
+            noise + scale * best < worst
+        });
+        if decided {
+            remaining.truncate(1);
+        }
+        decided
+    }
+
+    // Keeps searching from the current root until `stop` returns true. Nodes
+    // discarded by `move_down` are freed first, a bit at a time.
+    pub fn ponder<F: FnMut() -> bool>(&self, rng: &mut Box<Rng>, mut stop: F) {
+        let mut count = 0_u32;
+        let mut collected = false;
+
+        let root_ref = Rc::clone(&self.root.borrow());
+        loop {
+            if stop() {
+                break;
+            }
+            if !collected {
+                collected = self.collect_garbage_step(GARBAGE_STEP);
+                continue;
+            }
+            for _ in 0..PONDER_CHECK_INTERVAL {
+                MctsTree::iterate(&root_ref, rng);
+            }
+            count += PONDER_CHECK_INTERVAL;
+        }
+        eprintln!("pondered {}", count);
+    }
+
+    fn iterate(node: &Rc<MctsNode<P, S, R, A>>, rng: &mut Box<Rng>) {
+        let selected = MctsNode::select(Rc::clone(node));
+        let end_state = selected.state.clone();
+        if end_state.playable() {
+            let result = end_state.simulate_game(rng);
+            selected.backpropagate(&result);
+        } else {
+            selected.backpropagate(&end_state.outcome());
+        }
+    }
+
+    // The discarded siblings are kept until `collect_garbage`, freeing a big
+    // tree takes long enough to matter within a turn.
     pub fn move_down(&self, action: A) {
-        let child: Rc<MctsNode<P, S, R, A>> = Rc::clone(&self.root.borrow().children.borrow().get(&action).unwrap());
+        let root = Rc::clone(&self.root.borrow());
+        let child = root.children.borrow_mut().remove(&action);
+        let child = child.unwrap_or_else(|| Rc::new(MctsNode::new(root.state.perform_action_copy(&action))));
+
+        let mut garbage = self.garbage.borrow_mut();
+        garbage.extend(root.children.borrow_mut().drain().map(|(_, sibling)| sibling));
         self.root.replace(child);
     }
+
+    // Keeps one action of every class of equivalent root actions, the most
+    // visited one when the class was already searched.
+    pub fn collapse_root(&self, classes: &[Vec<A>]) {
+        let representatives = {
+            let root = self.root.borrow();
+            let children = root.children.borrow();
+            let visits = |action: &A| children.get(action).map_or(0., |child| child.visits.get());
+            classes
+                .iter()
+                .filter_map(|class| class.iter().max_by(|a, b| visits(a).total_cmp(&visits(b))).cloned())
+                .collect::<Vec<_>>()
+        };
+        self.restrict_root(&representatives);
+    }
+
+    // Limits the root to `allowed`, the removed subtrees are freed like
+    // discarded siblings.
+    pub fn restrict_root(&self, allowed: &[A]) {
+        let removed = self.root.borrow().restrict_actions(allowed);
+        self.garbage.borrow_mut().extend(removed);
+    }
+
+    // Starts over from `state`, the old tree is freed like discarded siblings.
+    pub fn reset(&self, state: S) {
+        let old_root = self.root.replace(Rc::new(MctsNode::new(state)));
+        self.garbage.borrow_mut().push(old_root);
+    }
+
+    pub fn collect_garbage(&self) {
+        while !self.collect_garbage_step(usize::MAX) {}
+    }
+
+    // Frees up to `budget` discarded nodes and returns true once nothing is
+    // left. Children are detached before a node is dropped, so a single step
+    // never recurses into a big subtree.
+    pub fn collect_garbage_step(&self, budget: usize) -> bool {
+        let mut garbage = self.garbage.borrow_mut();
+        for _ in 0..budget {
+            match garbage.pop() {
+                Some(node) => garbage.extend(node.children.borrow_mut().drain().map(|(_, child)| child)),
+                None => return true,
+            }
+        }
+        garbage.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game::game_action::Action;
+    use super::super::game::game_state::{State, UTTTResult};
+    use super::super::game::player::Player;
+    use super::*;
+
+    type Tree = MctsTree<Player, State, UTTTResult, Action>;
+
+    fn root_actions(tree: &Tree) -> Vec<Action> {
+        tree.root.borrow().children.borrow().keys().cloned().collect()
+    }
+
+    // Every class of the empty board is searched through exactly one action.
+    #[test]
+    fn collapse_keeps_one_action_per_class() {
+        let state = State::default();
+        let classes = state.equivalent_actions();
+        assert!(classes.len() < state.possible_actions().len());
+
+        let tree = Tree::new(state);
+        let mut rng: Box<Rng> = Box::new(Rng::with_seed(1));
+        tree.collapse_root(&classes);
+        tree.expand_iterations(500, &mut rng);
+
+        let actions = root_actions(&tree);
+        for class in &classes {
+            assert_eq!(class.iter().filter(|action| actions.contains(action)).count(), 1, "{:?}", class);
+        }
+        assert_eq!(actions.len(), classes.len());
+    }
+
+    #[test]
+    fn collapse_keeps_the_most_visited_action() {
+        let state = State::default();
+        let classes = state.equivalent_actions();
+        let tree = Tree::new(state);
+        let mut rng: Box<Rng> = Box::new(Rng::with_seed(2));
+        tree.expand_iterations(2000, &mut rng);
+
+        let visits = |action: &Action| tree.root.borrow().children.borrow()[action].visits.get();
+        let most_visited = classes
+            .iter()
+            .map(|class| class.iter().map(visits).fold(0., f64::max))
+            .collect::<Vec<_>>();
+        // otherwise any action of a class would do
+        assert!(classes.iter().any(|class| class.iter().any(|action| visits(action) != visits(&class[0]))));
+        tree.collapse_root(&classes);
+
+        let actions = root_actions(&tree);
+        assert_eq!(actions.len(), classes.len());
+        for (class, most_visited) in classes.iter().zip(most_visited) {
+            let kept = class.iter().find(|action| actions.contains(action)).unwrap();
+            assert_eq!(visits(kept), most_visited, "{:?}", class);
+        }
+    }
 }
@@ -0,0 +1,3 @@
+pub mod solver;
+
+pub use super::*;
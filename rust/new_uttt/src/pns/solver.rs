@@ -0,0 +1,291 @@
+use std::marker::PhantomData;
+use std::time::{self, Instant};
+
+use super::mcts::traits::*;
+
+const INFINITY: u32 = u32::MAX;
+pub const MAX_NODES: usize = 500_000;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Proof<A> {
+    Won(A),
+    Drawn(A),
+    Lost,
+    Unknown,
+}
+
+struct PnNode<S, A> {
+    state: S,
+    action: Option<A>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    or_node: bool,
+    pn: u32,
+    dn: u32,
+}
+
+// Proof-number search. Players are assumed to alternate, so OR and AND nodes
+// are told apart by their depth. A position is solved in two passes: first
+// trying to prove a win for the player to move, then trying to prove at least
+// a draw.
+pub struct PnSolver<P, S, R, A>
+where
+    P: GamePlayer<R>,
+    S: GameState<P, R, A>,
+    R: GameResult,
+    A: GameAction,
+{
+    pub max_nodes: usize,
+
+    types: PhantomData<(P, S, R, A)>,
+}
+
+impl<P, S, R, A> Default for PnSolver<P, S, R, A>
+where
+    P: GamePlayer<R>,
+    S: GameState<P, R, A>,
+    R: GameResult,
+    A: GameAction,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P, S, R, A> PnSolver<P, S, R, A>
+where
+    P: GamePlayer<R>,
+    S: GameState<P, R, A>,
+    R: GameResult,
+    A: GameAction,
+{
+    pub fn new() -> Self {
+        PnSolver {
+            max_nodes: MAX_NODES,
+            types: PhantomData,
+        }
+    }
+
+    pub fn solve(&self, state: &S, begin: Instant, duration: time::Duration) -> Proof<A> {
+        if !state.playable() {
+            return Proof::Lost;
+        }
+
+        match self.prove(state, 1., begin, duration) {
+            Some(Some(action)) => return Proof::Won(action),
+            Some(None) => {}
+            None => return Proof::Unknown,
+        }
+        match self.prove(state, 0.5, begin, duration) {
+            Some(Some(action)) => Proof::Drawn(action),
+            Some(None) => Proof::Lost,
+            None => Proof::Unknown,
+        }
+    }
+
+    // Tries to prove that the player to move reaches at least `target` reward.
+    // Returns the proving move, `Some(None)` when disproven and `None` when the
+    // node or time budget ran out first.
+    fn prove(&self, state: &S, target: f64, begin: Instant, duration: time::Duration) -> Option<Option<A>> {
+        let player = state.next_player();
+        let mut nodes = vec![PnNode {
+            state: state.clone(),
+            action: None,
+            parent: None,
+            children: Vec::new(),
+            or_node: true,
+            pn: 1,
+            dn: 1,
+        }];
+
+        let mut iterations = 0_u32;
+        while nodes[0].pn != 0 && nodes[0].dn != 0 {
+            iterations += 1;
+            if nodes.len() >= self.max_nodes
+                || (iterations & 63 == 0 && begin.elapsed() >= duration)
+            {
+                return None;
+            }
+
+            let leaf = Self::most_proving(&nodes);
+            Self::expand(&mut nodes, leaf, &player, target);
+            Self::update_ancestors(&mut nodes, leaf);
+        }
+
+        if nodes[0].pn == 0 {
+            let proving = nodes[0]
+                .children
+                .iter()
+                .find(|&&child| nodes[child].pn == 0)
+                .unwrap();
+            Some(nodes[*proving].action.clone())
+        } else {
+            Some(None)
+        }
+    }
+
+    fn most_proving(nodes: &[PnNode<S, A>]) -> usize {
+        let mut index = 0;
+        while !nodes[index].children.is_empty() {
+            let node = &nodes[index];
+            index = if node.or_node {
+                *node.children.iter().min_by_key(|&&child| nodes[child].pn).unwrap()
+            } else {
+                *node.children.iter().min_by_key(|&&child| nodes[child].dn).unwrap()
+            };
+        }
+        index
+    }
+
+    fn expand(nodes: &mut Vec<PnNode<S, A>>, index: usize, player: &P, target: f64) {
+        let or_node = !nodes[index].or_node;
+        let actions = nodes[index].state.possible_actions();
+        for action in actions {
+            let state = nodes[index].state.perform_action_copy(&action);
+            let (pn, dn) = if state.playable() {
+                (1, 1)
+            } else if player.reward(&state.outcome()) >= target {
+                (0, INFINITY)
+            } else {
+                (INFINITY, 0)
+            };
+
+            nodes.push(PnNode {
+                state,
+                action: Some(action),
+                parent: Some(index),
+                children: Vec::new(),
+                or_node,
+                pn,
+                dn,
+            });
+            let child = nodes.len() - 1;
+            nodes[index].children.push(child);
+        }
+
+        // a playable state without any action cannot be won from here
+        if nodes[index].children.is_empty() {
+            nodes[index].pn = INFINITY;
+            nodes[index].dn = 0;
+        }
+    }
+
+    fn update_ancestors(nodes: &mut [PnNode<S, A>], index: usize) {
+        let mut current = Some(index);
+        while let Some(index) = current {
+            let node = &nodes[index];
+            if !node.children.is_empty() {
+                let pns = node.children.iter().map(|&child| nodes[child].pn);
+                let dns = node.children.iter().map(|&child| nodes[child].dn);
+                let (pn, dn) = if node.or_node {
+                    (pns.min().unwrap(), dns.fold(0, u32::saturating_add))
+                } else {
+                    (pns.fold(0, u32::saturating_add), dns.min().unwrap())
+                };
+                nodes[index].pn = pn;
+                nodes[index].dn = dn;
+            }
+            current = nodes[index].parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::game::game_action::Action;
+    use super::super::game::game_state::{State, UTTTResult};
+    use super::super::game::player::Player;
+    use super::super::game::test_util::random_game;
+    use super::*;
+
+    type Solver = PnSolver<Player, State, UTTTResult, Action>;
+
+    // Exact reward of the player to move under perfect play, unless that takes
+    // more than `budget` nodes.
+    fn value(state: &State, budget: &mut u32) -> Option<f64> {
+        *budget = budget.checked_sub(1)?;
+        if !state.playable() {
+            return Some(state.next_player().reward(&state.outcome()));
+        }
+        let mut best = 0.;
+        for action in state.possible_actions() {
+            best = f64::max(best, 1. - value(&state.perform_action_copy(&action), budget)?);
+            if best == 1. {
+                break;
+            }
+        }
+        Some(best)
+    }
+
+    fn exact_value(state: &State) -> f64 {
+        let mut budget = u32::MAX;
+        value(state, &mut budget).unwrap()
+    }
+
+    // The last positions of random games that are small enough to solve by
+    // brute force, with their exact values.
+    fn endgames() -> Vec<(State, f64)> {
+        let mut positions = Vec::new();
+        for seed in 0..40 {
+            let moves = random_game(seed, &State::default());
+            let mut state = State::default();
+            for (ply, action) in moves.iter().enumerate() {
+                if ply + 6 >= moves.len() {
+                    let mut budget = 10_000;
+                    if let Some(exact) = value(&state, &mut budget) {
+                        positions.push((state.clone(), exact));
+                    }
+                }
+                state = state.perform_action_copy(action);
+            }
+        }
+        positions
+    }
+
+    fn solve(state: &State) -> Proof<Action> {
+        Solver::new().solve(state, Instant::now(), time::Duration::from_secs(60))
+    }
+
+    #[test]
+    fn proves_won_and_lost_positions() {
+        let positions = endgames();
+        assert!(positions.iter().any(|&(_, value)| value == 1.));
+        assert!(positions.iter().any(|&(_, value)| value == 0.));
+        for (state, expected) in positions {
+            match solve(&state) {
+                Proof::Won(action) => {
+                    assert_eq!(expected, 1., "{}", state);
+                    assert_eq!(exact_value(&state.perform_action_copy(&action)), 0., "{} {}", state, action);
+                }
+                Proof::Lost => assert_eq!(expected, 0., "{}", state),
+                Proof::Drawn(_) => assert_eq!(expected, 0.5, "{}", state),
+                Proof::Unknown => panic!("no proof for {}", state),
+            }
+        }
+    }
+
+    #[test]
+    fn proves_drawn_positions() {
+        let drawn = endgames().into_iter().filter(|&(_, value)| value == 0.5).collect::<Vec<_>>();
+        assert!(!drawn.is_empty());
+        for (state, _) in drawn {
+            match solve(&state) {
+                Proof::Drawn(action) => {
+                    assert_eq!(exact_value(&state.perform_action_copy(&action)), 0.5, "{} {}", state, action)
+                }
+                proof => panic!("{:?} for {}", proof, state),
+            }
+        }
+    }
+
+    #[test]
+    fn gives_up_at_the_limits() {
+        let state = State::default();
+        let mut solver = Solver::new();
+        solver.max_nodes = 1000;
+        assert_eq!(solver.solve(&state, Instant::now(), time::Duration::from_secs(60)), Proof::Unknown);
+
+        let solver = Solver::new();
+        assert_eq!(solver.solve(&state, Instant::now(), time::Duration::ZERO), Proof::Unknown);
+    }
+}
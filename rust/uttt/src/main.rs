@@ -1,8 +1,10 @@
 extern crate rand;
+
 use rand::prelude::{IteratorRandom, ThreadRng};
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::vec::Vec;
+use std::io::BufRead;
 use std::{io, time};
 
 // based on: https://github.com/nelhage/ultimattt/blob/master/src/lib/game.rs
@@ -206,7 +208,7 @@ impl GlobalStates {
 
                 if win_masks_for_move(bit)
                     .iter()
-                    .any(|&win_mask| self.x & win_mask == win_mask)
+                    .any(|&win_mask| win_mask & !self.x == 0)
                 {
                     return GameState::Won(Player::X);
                 }
@@ -229,7 +231,7 @@ impl GlobalStates {
 
                 if win_masks_for_move(bit)
                     .iter()
-                    .any(|&win_mask| self.o & win_mask == win_mask)
+                    .any(|&win_mask| win_mask & !self.o == 0)
                 {
                     return GameState::Won(Player::O);
                 }
@@ -528,46 +530,87 @@ impl Node {
     }
 }
 
-#[allow(dead_code)]
+// The part of the turn input this bot uses: the opponent's last move, `None`
+// on the first turn when we start. The valid actions are read and skipped.
+struct TurnInput {
+    opponent: Option<(usize, usize)>,
+}
+
+enum InputError {
+    // the referee closed stdin, the game is over
+    Eof,
+    Malformed(String),
+}
+
+fn read_line(input: &mut impl BufRead) -> Result<String, InputError> {
+    let mut line = String::new();
+    match input.read_line(&mut line) {
+        Ok(0) => Err(InputError::Eof),
+        Ok(_) => Ok(line),
+        Err(error) => Err(InputError::Malformed(error.to_string())),
+    }
+}
+
+fn read_turn(input: &mut impl BufRead) -> Result<TurnInput, InputError> {
+    let line = read_line(input)?;
+    let values = line
+        .split_ascii_whitespace()
+        .map(|value| value.parse::<i32>())
+        .collect::<Result<Vec<_>, _>>();
+    let opponent = match values.as_deref() {
+        Ok([-1, -1]) => None,
+        Ok(&[row, col]) if (0..9).contains(&row) && (0..9).contains(&col) => Some((row as usize, col as usize)),
+        _ => return Err(InputError::Malformed(format!("bad opponent move {:?}", line.trim_end()))),
+    };
+
+    let line = read_line(input)?;
+    let count = line
+        .trim()
+        .parse::<usize>()
+        .map_err(|_| InputError::Malformed(format!("bad action count {:?}", line.trim_end())))?;
+    for _ in 0..count {
+        read_line(input)?;
+    }
+    Ok(TurnInput { opponent })
+}
+
 //#[inline]
-fn read_input() -> (String, String) {
-    let mut input_line = String::new();
-    io::stdin().read_line(&mut input_line).unwrap();
-    let inputs = input_line.split(' ').collect::<Vec<_>>();
-    let mut input_line_2 = String::new();
-    io::stdin().read_line(&mut input_line_2).unwrap();
-    let valid_action_count = input_line_2.trim().parse::<i32>().unwrap();
-    for _ in 0..valid_action_count as usize {
-        io::stdin().read_line(&mut input_line_2).unwrap();
-    }
-
-    (inputs[0].to_string(), inputs[1].to_string())
+fn read_input(input: &mut impl BufRead) -> Option<TurnInput> {
+    match read_turn(input) {
+        Ok(turn) => Some(turn),
+        Err(InputError::Eof) => None,
+        Err(InputError::Malformed(reason)) => {
+            eprintln!("{}", reason);
+            None
+        }
+    }
 }
 
 #[allow(dead_code)]
 //#[inline]
 fn codingame() {
     let mut rng = rand::thread_rng();
-    let inputs = read_input();
+    let mut input = io::stdin().lock();
+    let turn = match read_input(&mut input) {
+        Some(turn) => turn,
+        None => return,
+    };
 
     let mut game = Game::default();
     let mut root: &mut Node = &mut Node::new();
     let mut m: &Move;
 
-    match inputs.0.chars().next() {
-        Some('-') => {
+    match turn.opponent {
+        None => {
             game.inplace_move(&Move::from_coords(4, LOCAL_MOVES[4]));
             root.unvisited_moves = game.all_moves();
             root.run(&mut game, 999999995, &mut rng);
             println!("4 4");
         }
-        Some(_) => {
-            let opponent_row = inputs.0.parse::<i8>().unwrap();
-            let opponent_col = inputs.1.parse::<i8>().unwrap();
-
+        Some((opponent_row, opponent_col)) => {
             game.inplace_move(&Move::from_coords(
-                (opponent_col / 3 + (opponent_row / 3) * 3) as usize,
-                LOCAL_MOVES[(opponent_col % 3 + (opponent_row % 3) * 3) as usize],
+                opponent_col / 3 + (opponent_row / 3) * 3,
+                LOCAL_MOVES[opponent_col % 3 + (opponent_row % 3) * 3],
             ));
             root.unvisited_moves = game.all_moves();
             root.run(&mut game, 999999997, &mut rng);
@@ -579,17 +622,17 @@ fn codingame() {
             game.inplace_move(m);
             m.print();
         }
-        _ => panic!(),
     }
 
     // game loop
-    loop {
-        let inputs = read_input();
-        let opponent_row = inputs.0.trim().parse::<i8>().unwrap();
-        let opponent_col = inputs.1.trim().parse::<i8>().unwrap();
+    while let Some(turn) = read_input(&mut input) {
+        let (opponent_row, opponent_col) = match turn.opponent {
+            Some(opponent) => opponent,
+            None => return,
+        };
         let opp_move = Move::from_coords(
-            (opponent_col / 3 + (opponent_row / 3) * 3) as usize,
-            LOCAL_MOVES[(opponent_col % 3 + (opponent_row % 3) * 3) as usize],
+            opponent_col / 3 + (opponent_row / 3) * 3,
+            LOCAL_MOVES[opponent_col % 3 + (opponent_row % 3) * 3],
         );
         game.inplace_move(&opp_move);
         root = root.children.get_mut(&opp_move).unwrap();
@@ -603,6 +646,7 @@ fn codingame() {
     }
 }
 
+#[allow(dead_code)]
 fn perf_test() {
     let mut rng = rand::thread_rng();
     let mut root = &mut Node::new();